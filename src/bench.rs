@@ -0,0 +1,157 @@
+use std::{
+    io::Write,
+    fs::{self,File},
+    time::{Instant,Duration}
+};
+
+use crate::{
+    option,
+    BOT_TIMEOUT,
+    engine::{Game,GameFunctionality,DeathCause},
+    map::{Map,MapFunctionality},
+    bots::{Controller,BOTS,bot_by_name},
+    external::{ExternalBot,csv_field},
+    gym::{IDLE_LIMIT,death_name}
+};
+
+const GAMES:u32 = 10; //default for --games
+
+struct Record {
+    seed:u64,
+    score:u16,
+    length:usize,
+    ticks:u32,
+    death:Option<DeathCause>,
+    timeout:bool
+}
+
+//plays one game as fast as bot can think
+fn play(bot:&mut dyn Controller,map:&Map,seed:u64,max_idle:u32) -> Record {
+    let mut game = Game::new(map,seed,1);
+    let mut idle = 0;
+    loop {
+        let decision = bot.decide(&game.view(0));
+        let step = game.step(&[decision]);
+        let death = step.deaths[0];
+        idle = if step.ate[0] {0} else {idle + 1};
+        if death.is_some() || idle >= max_idle {
+            let snake = &game.snakes[0];
+            return Record {
+                seed,
+                score:snake.score,
+                length:snake.body.len() + 1,
+                ticks:game.tick,
+                death,
+                timeout:death.is_none()
+            }
+        }
+    }
+}
+
+fn median(values:&mut [u16]) -> f32 {
+    values.sort();
+    let middle = values.len()/2;
+    if values.len() % 2 == 0 {
+        (values[middle - 1] as f32 + values[middle] as f32) / 2.0
+    } else {
+        values[middle] as f32
+    }
+}
+
+//`snake bench` - plays a bunch of seeded games for every bot on every map and tells who is better
+pub fn run() {
+    let bots = option("--bots").unwrap_or(
+        if option("--bot-cmd").is_some() {String::new()} else {BOTS.join(",")}
+    );
+    let maps = option("--maps").unwrap_or_default();
+    let games = option("--games").and_then(|n| n.parse().ok()).unwrap_or(GAMES).max(1);
+    let seed:u64 = option("--seed").and_then(|n| n.parse().ok()).unwrap_or(0);
+    let max_idle = option("--max-idle").and_then(|n| n.parse().ok()).unwrap_or(IDLE_LIMIT);
+    let timeout = option("--bot-timeout").and_then(|ms| ms.parse().ok()).unwrap_or(BOT_TIMEOUT);
+    let mut csv = option("--csv").map(|file| match File::create(&file) {
+        Ok(file) => file,
+        Err(error) => {
+            eprintln!("Can't create \"{}\": {}",file,error);
+            std::process::exit(1);
+        }
+    });
+    if let Some(file) = &mut csv {
+        writeln!(file,"bot,map,seed,score,length,ticks,death").ok();
+    }
+
+    //empty field would make a missing map look like it was played on
+    let maps = if maps.is_empty() {
+        vec![("default".to_string(),Map::new())]
+    } else {
+        let mut loaded = vec![];
+        for file in maps.split(',').filter(|file| !file.is_empty()) {
+            match fs::read_to_string(file) {
+                Ok(text) => loaded.push((file.to_string(),Map::parse(&text))),
+                Err(error) => {
+                    eprintln!("Can't read map \"{}\": {}",file,error);
+                    std::process::exit(1);
+                }
+            }
+        }
+        loaded
+    };
+    //external bot can take part too, it's called by its command
+    let mut contestants:Vec<(String,Box<dyn Controller>)> = vec![];
+    for name in bots.split(',').filter(|name| !name.is_empty()) {
+        match bot_by_name(name) {
+            Some(bot) => contestants.push((name.to_string(),bot)),
+            None => {
+                eprintln!("Unknown bot \"{}\", available bots: {}",name,BOTS.join(", "));
+                return
+            }
+        }
+    }
+    if let Some(command) = option("--bot-cmd") {
+        match ExternalBot::spawn(&command,Duration::from_millis(timeout)) {
+            Ok(bot) => contestants.push((command,Box::new(bot))),
+            Err(error) => {
+                eprintln!("Can't start bot \"{}\": {}",command,error);
+                return
+            }
+        }
+    }
+
+    println!(
+        "{:<16} {:<16} {:>6} {:>6} {:>6} {:>7} {:>5} {:>5} {:>8} {:>7} {:>10}",
+        "bot","map","mean","median","max","length","wall","self","obstacle","timeout","steps/s"
+    );
+    for (name,bot) in &mut contestants {
+        for (map_name,map) in &maps {
+            let start = Instant::now();
+            let records = (0..games as u64)
+                .map(|i| play(bot.as_mut(),map,seed + i,max_idle))
+                .collect::<Vec<Record>>();
+            let seconds = start.elapsed().as_secs_f64();
+            let mut scores = records.iter().map(|r| r.score).collect::<Vec<u16>>();
+            let count = |cause:DeathCause| records.iter().filter(|r| r.death == Some(cause)).count();
+            let steps = records.iter().map(|r| r.ticks as f64).sum::<f64>();
+            println!(
+                "{:<16} {:<16} {:>6.1} {:>6.1} {:>6} {:>7.1} {:>5} {:>5} {:>8} {:>7} {:>10.0}",
+                name,
+                map_name,
+                scores.iter().map(|s| *s as f32).sum::<f32>() / games as f32,
+                median(&mut scores),
+                scores.iter().max().unwrap(),
+                records.iter().map(|r| r.length as f32).sum::<f32>() / games as f32,
+                count(DeathCause::Wall),
+                count(DeathCause::Snake),
+                count(DeathCause::Obstacle),
+                records.iter().filter(|r| r.timeout).count(),
+                steps / seconds.max(f64::EPSILON)
+            );
+            if let Some(file) = &mut csv {
+                for r in &records {
+                    writeln!(
+                        file,"{},{},{},{},{},{},{}",
+                        csv_field(name),csv_field(map_name),r.seed,r.score,r.length,r.ticks,death_name(&r.death,r.timeout)
+                    ).ok();
+                }
+            }
+        }
+    }
+}
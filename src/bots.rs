@@ -0,0 +1,385 @@
+use std::collections::VecDeque;
+
+use rand::{Rng,SeedableRng,rngs::StdRng,seq::SliceRandom};
+
+use crate::{
+    Pos,
+    Direction,
+    DirectionFunctionality,
+    Snake,
+    Fruit,
+    GAME_FIELD_SIZE,
+    next_pos,
+    map::Walls,
+    obstacles::{Obstacle,ObstacleFunctionality}
+};
+
+const DIRECTIONS:[Direction;4] = [Direction::Up,Direction::Right,Direction::Down,Direction::Left];
+const CELLS:usize = GAME_FIELD_SIZE*GAME_FIELD_SIZE; //longest possible path
+pub const BOTS:[&str;3] = ["autopilot","greedy","random"]; //names for --bot
+pub const SKILLS:[&str;3] = ["easy","normal","hard"]; //for --rival-skill
+const EASY_MISTAKES:f64 = 0.25; //how often easy rival forgets where it's going
+
+//what bot is allowed to know about the game
+pub struct GameView<'a> {
+    pub walls:&'a Walls,
+    pub snake:&'a Snake,
+    pub rivals:Vec<&'a Snake>, //other snakes that are still alive
+    pub fruit:&'a Fruit,
+    pub leftovers:&'a [Pos],
+    pub obstacles:&'a [Obstacle],
+    pub score:u16,
+    pub tick:u32,
+    pub seed:u64 //of the game, mixed with the player number
+}
+
+//anything that can steer the snake instead of keyboard
+//None means "keep going where you are going"
+pub trait Controller {
+    fn decide(&mut self,view:&GameView) -> Option<Direction>;
+}
+
+pub fn bot_by_name(name:&str) -> Option<Box<dyn Controller>> {
+    match name {
+        "autopilot" => Some(Box::new(Autopilot::default())),
+        "greedy" => Some(Box::new(Greedy::default())),
+        "random" => Some(Box::new(Random::default())),
+        _ => None
+    }
+}
+
+//rival snakes are just bots, better ones for higher skill
+pub fn rival_by_skill(skill:&str) -> Option<Box<dyn Controller>> {
+    match skill {
+        "easy" => Some(Box::new(Clumsy {bot:Greedy::default(),forecast:Forecast::default(),mistakes:EASY_MISTAKES,dice:Dice::default()})),
+        "normal" => Some(Box::new(Greedy::default())),
+        "hard" => Some(Box::new(Autopilot::default())),
+        _ => None
+    }
+}
+
+//everything worth eating
+fn targets(view:&GameView) -> Vec<Pos> {
+    std::iter::once(view.fruit.pos).chain(view.leftovers.iter().copied()).collect()
+}
+
+type Grid<T> = [[T;GAME_FIELD_SIZE];GAME_FIELD_SIZE];
+
+fn obstacle_frame(obstacles:&[Obstacle]) -> Grid<bool> {
+    let mut frame = [[false;GAME_FIELD_SIZE];GAME_FIELD_SIZE];
+    for cell in obstacles.iter().flat_map(|o| o.cells()) {
+        frame[cell.y as usize][cell.x as usize] = true;
+    }
+    frame
+}
+
+//where obstacles will be for the longest path, worked out once and then moved on by a tick
+#[derive(Default)]
+struct Forecast {
+    walls:Option<Walls>, //obstacles bounce off them, so the field shrinking starts it all over
+    now:Vec<Obstacle>, //what the first frame shows
+    last:Vec<Obstacle>, //what comes after the last frame
+    frames:Vec<Grid<bool>> //frames[t] = cells taken by obstacles after t moves
+}
+
+impl Forecast {
+    fn update(&mut self,walls:&Walls,obstacles:&[Obstacle]) -> &[Grid<bool>] {
+        if self.walls.as_ref() == Some(walls) && self.now == obstacles {return &self.frames} //asked twice in a tick
+        let mut next = self.now.clone();
+        for obstacle in &mut next {
+            obstacle.advance(walls);
+        }
+        if self.walls.as_ref() == Some(walls) && !self.frames.is_empty() && next == obstacles {
+            self.frames.remove(0);
+            self.frames.push(obstacle_frame(&self.last));
+            for obstacle in &mut self.last {
+                obstacle.advance(walls);
+            }
+        } else {
+            self.frames.clear();
+            self.last = obstacles.to_vec();
+            if !obstacles.is_empty() {
+                for _ in 0..=CELLS {
+                    self.frames.push(obstacle_frame(&self.last));
+                    for obstacle in &mut self.last {
+                        obstacle.advance(walls);
+                    }
+                }
+            }
+        }
+        self.walls = Some(*walls);
+        self.now = obstacles.to_vec();
+        &self.frames
+    }
+}
+
+//everything the search needs to know about the field in time
+struct Field<'a> {
+    walls:&'a Walls,
+    free_after:Grid<usize>, //how many moves it takes for body part to leave the cell
+    obstacles:&'a [Grid<bool>], //obstacles[t] = cells taken by obstacles after t moves
+    length:usize
+}
+
+impl<'a> Field<'a> {
+    fn new(walls:&'a Walls,body:&VecDeque<Pos>,head:&Pos,obstacles:&'a [Grid<bool>],rivals:&[&Snake]) -> Field<'a> {
+        let mut free_after = [[0;GAME_FIELD_SIZE];GAME_FIELD_SIZE];
+        for (i,part) in body.iter().enumerate() { //tail is in front, so it leaves first
+            free_after[part.y as usize][part.x as usize] = i + 1;
+        }
+        free_after[head.y as usize][head.x as usize] = body.len() + 1;
+        for rival in rivals {
+            for (i,part) in rival.body.iter().enumerate() {
+                free_after[part.y as usize][part.x as usize] = i + 1;
+            }
+            //rival can go anywhere next tick, better not to meet it head to head
+            for dir in &DIRECTIONS {
+                let next = next_pos(&rival.pos,dir);
+                let cell = &mut free_after[next.y as usize][next.x as usize];
+                *cell = (*cell).max(2);
+            }
+            free_after[rival.pos.y as usize][rival.pos.x as usize] = rival.body.len() + 1;
+        }
+        Field {walls,free_after,obstacles,length:body.len() + 1}
+    }
+
+    //body stays in the cell for a while after head leaves it, obstacles must not come there meanwhile
+    fn is_free(&self,pos:&Pos,time:usize) -> bool {
+        self.is_free_for(pos,time,self.length + 1)
+    }
+
+    fn is_free_for(&self,pos:&Pos,time:usize,stay:usize) -> bool {
+        let (x,y) = (pos.x as usize,pos.y as usize);
+        !self.walls[y][x] &&
+            self.free_after[y][x] <= time &&
+            !self.obstacles.iter().skip(time).take(stay).any(|frame| frame[y][x])
+    }
+
+    //shortest way to the closest of the points, snake can't wait so every step is one tick
+    fn path(&self,from:&Pos,to:&[Pos]) -> Option<Vec<Direction>> {
+        let mut came_from:Grid<Option<(Pos,usize)>> = [[None;GAME_FIELD_SIZE];GAME_FIELD_SIZE];
+        let mut visited = [[false;GAME_FIELD_SIZE];GAME_FIELD_SIZE];
+        let mut queue = VecDeque::from([(*from,0usize)]);
+        visited[from.y as usize][from.x as usize] = true;
+        while let Some((pos,time)) = queue.pop_front() {
+            if to.contains(&pos) {
+                let mut path = vec![];
+                let mut cur = pos;
+                while let Some((prev,dir)) = came_from[cur.y as usize][cur.x as usize] {
+                    path.push(DIRECTIONS[dir].copy());
+                    cur = prev;
+                }
+                path.reverse();
+                return Some(path)
+            }
+            for (i,dir) in DIRECTIONS.iter().enumerate() {
+                let next = next_pos(&pos,dir);
+                let (x,y) = (next.x as usize,next.y as usize);
+                if visited[y][x] || !self.is_free(&next,time + 1) {continue}
+                visited[y][x] = true;
+                came_from[y][x] = Some((pos,i));
+                queue.push_back((next,time + 1));
+            }
+        }
+        None
+    }
+
+    //how many cells snake can reach from this point
+    fn space(&self,from:&Pos) -> usize {
+        let mut visited = [[false;GAME_FIELD_SIZE];GAME_FIELD_SIZE];
+        let mut queue = VecDeque::from([(*from,1usize)]);
+        visited[from.y as usize][from.x as usize] = true;
+        let mut count = 0;
+        while let Some((pos,time)) = queue.pop_front() {
+            count += 1;
+            for dir in &DIRECTIONS {
+                let next = next_pos(&pos,dir);
+                let (x,y) = (next.x as usize,next.y as usize);
+                if visited[y][x] || !self.is_free(&next,time + 1) {continue}
+                visited[y][x] = true;
+                queue.push_back((next,time + 1));
+            }
+        }
+        count
+    }
+}
+
+//moves snake body along the path, as if it ate fruit on the last step
+fn follow(snake:&Snake,path:&[Direction]) -> (Pos,VecDeque<Pos>) {
+    let mut head = snake.pos;
+    let mut body = snake.body.clone();
+    for (i,dir) in path.iter().enumerate() {
+        body.push_back(head);
+        head = next_pos(&head,dir);
+        if i + 1 < path.len() {body.pop_front();}
+    }
+    (head,body)
+}
+
+//goes for the fruit if it can get back to its own tail after eating,
+//otherwise chases the tail until the way to the fruit gets safe
+#[derive(Default)]
+pub struct Autopilot {
+    forecast:Forecast
+}
+
+impl Controller for Autopilot {
+    fn decide(&mut self,view:&GameView) -> Option<Direction> {
+        let snake = view.snake;
+        let obstacles = self.forecast.update(view.walls,view.obstacles);
+        let field = Field::new(view.walls,&snake.body,&snake.pos,obstacles,&view.rivals);
+        if let Some(path) = field.path(&snake.pos,&targets(view)) {
+            let (head,body) = follow(snake,&path);
+            let future = Field::new(view.walls,&body,&head,&[],&[]);
+            if future.path(&head,&[*body.front().unwrap()]).is_some() {
+                return path.first().map(|dir| dir.copy())
+            }
+        }
+        if let Some(path) = field.path(&snake.pos,&[*snake.body.front().unwrap()]) {
+            return path.first().map(|dir| dir.copy())
+        }
+        //no good way out, just survive this tick and go where there is more space
+        DIRECTIONS.iter()
+            .filter(|dir| field.is_free_for(&next_pos(&snake.pos,dir),1,2))
+            .max_by_key(|dir| {
+                let next = next_pos(&snake.pos,dir);
+                (field.is_free(&next,1),field.space(&next))
+            })
+            .map(|dir| dir.copy())
+    }
+}
+
+//distance between two points on the field where edges are connected
+fn distance(a:&Pos,b:&Pos) -> usize {
+    let size = GAME_FIELD_SIZE as i32;
+    let dx = (a.x as i32 - b.x as i32).abs();
+    let dy = (a.y as i32 - b.y as i32).abs();
+    (dx.min(size - dx) + dy.min(size - dy)) as usize
+}
+
+//doesn't plan anything, just makes the move that gets it closer to the fruit
+#[derive(Default)]
+pub struct Greedy {
+    forecast:Forecast
+}
+
+impl Controller for Greedy {
+    fn decide(&mut self,view:&GameView) -> Option<Direction> {
+        let obstacles = self.forecast.update(view.walls,view.obstacles);
+        let field = Field::new(view.walls,&view.snake.body,&view.snake.pos,obstacles,&view.rivals);
+        let targets = targets(view);
+        DIRECTIONS.iter()
+            .filter(|dir| field.is_free_for(&next_pos(&view.snake.pos,dir),1,2))
+            .min_by_key(|dir| targets.iter().map(|target| distance(&next_pos(&view.snake.pos,dir),target)).min())
+            .map(|dir| dir.copy())
+    }
+}
+
+//wanders around, but at least doesn't bite walls on purpose
+#[derive(Default)]
+pub struct Random {
+    forecast:Forecast,
+    dice:Dice
+}
+
+impl Controller for Random {
+    fn decide(&mut self,view:&GameView) -> Option<Direction> {
+        wander(&mut self.forecast,view,self.dice.roll(view))
+    }
+}
+
+//any way that isn't deadly right away
+fn wander(forecast:&mut Forecast,view:&GameView,rng:&mut StdRng) -> Option<Direction> {
+    let obstacles = forecast.update(view.walls,view.obstacles);
+    let field = Field::new(view.walls,&view.snake.body,&view.snake.pos,obstacles,&view.rivals);
+    let free = DIRECTIONS.iter()
+        .filter(|dir| field.is_free_for(&next_pos(&view.snake.pos,dir),1,2))
+        .collect::<Vec<&Direction>>();
+    free.choose(rng).map(|dir| dir.copy())
+}
+
+//another bot that sometimes wanders off instead of doing what it should,
+//the same dice picks where, so it's rolled every tick and never misses a new game
+pub struct Clumsy<T:Controller> {
+    bot:T,
+    forecast:Forecast,
+    mistakes:f64,
+    dice:Dice
+}
+
+impl<T:Controller> Controller for Clumsy<T> {
+    fn decide(&mut self,view:&GameView) -> Option<Direction> {
+        let rng = self.dice.roll(view);
+        if rng.gen_bool(self.mistakes) {
+            wander(&mut self.forecast,view,rng)
+        } else {
+            self.bot.decide(view)
+        }
+    }
+}
+
+//random numbers that start over from the game seed with every new game,
+//so the same seed is played the same way no matter what was played before
+#[derive(Default)]
+struct Dice {
+    rng:Option<StdRng>,
+    seed:u64,
+    tick:u32
+}
+
+impl Dice {
+    fn roll(&mut self,view:&GameView) -> &mut StdRng {
+        //another seed is another game, and ticks only go up during one, so going back means a new one too
+        if self.rng.is_none() || view.seed != self.seed || view.tick <= self.tick {
+            self.rng = Some(StdRng::seed_from_u64(view.seed));
+            self.seed = view.seed;
+        }
+        self.tick = view.tick;
+        self.rng.as_mut().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        engine::{Game,GameFunctionality},
+        external::direction_name,
+        map::{Map,MapFunctionality}
+    };
+
+    //every move the bot made until it died or gave up
+    fn play(bot:&mut dyn Controller,seed:u64,ticks:u32) -> Vec<String> {
+        let mut game = Game::new(&Map::new(),seed,1);
+        let mut moves = vec![];
+        while game.snakes[0].alive && game.tick < ticks {
+            let decision = bot.decide(&game.view(0));
+            moves.push(decision.as_ref().map_or("-".to_string(),|dir| direction_name(dir).to_string()));
+            game.step(&[decision]);
+        }
+        moves
+    }
+
+    #[test]
+    fn same_seed_same_moves() {
+        let first = play(rival_by_skill("easy").unwrap().as_mut(),5,60);
+        let mut bot = rival_by_skill("easy").unwrap();
+        play(bot.as_mut(),7,200);
+        play(bot.as_mut(),9,3);
+        assert!(play(bot.as_mut(),5,60) == first);
+        let mut random = Random::default();
+        let first = play(&mut random,11,40);
+        play(&mut random,12,80);
+        assert!(play(&mut random,11,40) == first);
+    }
+
+    #[test]
+    fn new_seed_new_dice() {
+        let (first,second) = (Game::new(&Map::new(),1,1),Game::new(&Map::new(),2,1));
+        let mut dice = Dice::default();
+        dice.roll(&GameView {tick:5,..first.view(0)});
+        //a later tick, but another game
+        let rolled = dice.roll(&GameView {tick:9,..second.view(0)}).gen::<u64>();
+        assert_eq!(rolled,StdRng::seed_from_u64(2).gen::<u64>());
+    }
+}
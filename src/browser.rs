@@ -0,0 +1,122 @@
+use std::{
+    io::{Write,stdout},
+    time::Duration
+};
+
+use crossterm::{
+    execute, queue,
+    terminal::{Clear,ClearType},
+    cursor::MoveTo,
+    event::{read,Event,KeyCode},
+    style::{Print,Stylize}
+};
+
+use crate::{
+    GAME_FIELD_SIZE,
+    GLOBAL_OFFSET_X,
+    GLOBAL_OFFSET_Y,
+    NONE,
+    draw_scaled_map,
+    general::{error_handling::*,graphics::*,input::*},
+    map::{Map,MapFunctionality,map_path,list_maps},
+    theme::Theme
+};
+
+const LIST_X:u16 = GLOBAL_OFFSET_X;
+const LIST_Y:u16 = GLOBAL_OFFSET_Y + 2;
+const LIST_ROWS:usize = 20; //more maps than that scroll
+const LIST_WIDTH:usize = 18; //longest name and the current map mark
+const PREVIEW_X:u16 = LIST_X + LIST_WIDTH as u16 + 3;
+const PREVIEW_SCALE:usize = 2; //every sign shows 2x2 cells, so the whole map fits next to the list
+const PREVIEW_SIZE:u16 = GAME_FIELD_SIZE.div_ceil(PREVIEW_SCALE) as u16;
+const HINTS:&str = "Arrows, Mouse - choose map\nENTER, LMB - PLAY and EDIT it\nESC - back";
+const PROMPT_LENGTH:usize = 24;
+
+fn draw_list(maps:&[String],current:&str,selected:usize,offset:usize,theme:&Theme) {
+    for row in 0..LIST_ROWS {
+        let line = match maps.get(offset + row) {
+            Some(name) => format!("{}{:<width$}",if name == current {'*'} else {' '},name,width = LIST_WIDTH - 1),
+            None => " ".repeat(LIST_WIDTH)
+        };
+        queue!(
+            stdout(),
+            MoveTo(LIST_X,LIST_Y + row as u16),
+            Print(if offset + row == selected {theme.highlight(line)} else {line.reset()})
+        ).handle();
+    }
+}
+
+fn draw_preview(name:&str,theme:&Theme) {
+    let map = Map::load(&map_path(name));
+    draw_scaled_map(&map.walls,PREVIEW_X,LIST_Y,PREVIEW_SCALE,theme);
+    queue!(
+        stdout(),
+        MoveTo(PREVIEW_X,LIST_Y + PREVIEW_SIZE + 1),
+        Clear(ClearType::UntilNewLine),
+        Print(format!("obstacles: {}",map.obstacles.len()))
+    ).handle();
+}
+
+//level select, tells which map was chosen, current one is marked with *
+pub fn browse(current:&str,theme:&Theme) -> Option<String> {
+    let maps = list_maps();
+    if maps.is_empty() {return None}
+    let mut selected = maps.iter().position(|name| name == current).unwrap_or(0);
+    let mut offset = selected.saturating_sub(LIST_ROWS - 1);
+    let mut cursor = Cursor {x:LIST_X,y:LIST_Y + (selected - offset) as u16,hover:NONE};
+    execute!(stdout(),Clear(ClearType::All),MoveTo(LIST_X,GLOBAL_OFFSET_Y),Print("Maps:")).handle();
+    draw_simple_ascii_picture(HINTS,PREVIEW_X,LIST_Y + PREVIEW_SIZE + 3);
+    loop {
+        draw_list(&maps,current,selected,offset,theme);
+        draw_preview(&maps[selected],theme);
+        stdout().flush().handle();
+        let input_result = cursor_input(&mut cursor,Duration::MAX);
+        let on_list = cursor.x < PREVIEW_X;
+        //cursor going past the first or the last row scrolls the list
+        let row = cursor.y as i32 - LIST_Y as i32;
+        if row < 0 {
+            offset = offset.saturating_sub(1);
+        } else if row >= LIST_ROWS as i32 {
+            offset = (offset + 1).min(maps.len().saturating_sub(LIST_ROWS));
+        }
+        selected = (offset + row.clamp(0,LIST_ROWS as i32 - 1) as usize).min(maps.len() - 1);
+        cursor.x = LIST_X;
+        cursor.y = LIST_Y + (selected - offset) as u16;
+        match input_result {
+            InputResult::Click if on_list => return Some(maps[selected].clone()),
+            InputResult::Abort => return None,
+            _ => ()
+        }
+    }
+}
+
+//asks for a line of text right where it's drawn, ESC gives up
+pub fn prompt(x:u16,y:u16,question:&str,initial:&str) -> Option<String> {
+    let mut text = initial.to_string();
+    let answer = loop {
+        queue!(
+            stdout(),
+            MoveTo(x,y),
+            Clear(ClearType::UntilNewLine),
+            Print(question),
+            Print(" "),
+            Print(&text),
+            Print("_".slow_blink())
+        ).handle();
+        stdout().flush().handle();
+        let event = match read() {
+            Ok(Event::Key(event)) => event,
+            Ok(_) => continue,
+            Err(_) => break None
+        };
+        match event.code {
+            KeyCode::Enter => break Some(text),
+            KeyCode::Esc => break None,
+            KeyCode::Backspace => {text.pop();},
+            KeyCode::Char(ch) if text.chars().count() < PROMPT_LENGTH => text.push(ch),
+            _ => ()
+        }
+    };
+    queue!(stdout(),MoveTo(x,y),Clear(ClearType::UntilNewLine)).handle();
+    answer
+}
@@ -0,0 +1,312 @@
+use std::{
+    io::{Write,stdout},
+    fs,
+    time::{SystemTime,Duration}
+};
+
+use crossterm::{
+    execute, queue,
+    terminal::{Clear,ClearType},
+    cursor::MoveTo,
+    style::{Print,Stylize}
+};
+
+use crate::{
+    GAME_TICK,
+    GAME_TIME_OFFSET,
+    GAME_FIELD_OFFSET_Y,
+    GAME_INFO_X,
+    GLOBAL_OFFSET_Y,
+    CAMPAIGN_DIR,
+    FILE_PROGRESS,
+    start_game,
+    draw_step,
+    draw_box,
+    wait_for_start,
+    confirm,
+    game_seconds,
+    general::{error_handling::*,input::*,list::*},
+    engine::{Game,GameFunctionality,Rules},
+    map::{Map,MapFunctionality,write_atomically},
+    theme::Theme
+};
+
+const FILE_LEVELS:&str = "levels.txt"; //in CAMPAIGN_DIR, next to the maps
+const MAX_STARS:u8 = 3;
+const NAME_WIDTH:usize = 12;
+const GOAL_WIDTH:usize = 26;
+const HINTS:&str = "Arrows, Mouse - choose level\nENTER, LMB - play it\nESC - back";
+const GOAL_OFFSET_Y:u16 = GAME_FIELD_OFFSET_Y + 2;
+
+//what has to be done to clear the level
+#[derive(Clone,Copy)]
+enum Goal {
+    Apples(u16),
+    Survive(u32), //seconds
+    Collect(u16) //that many fruit are scattered at the start and none come back
+}
+
+//one line of the levels file: <map> <apples|survive|collect> <target> <2 stars> <3 stars>
+//stars are seconds to beat, except for survive where they're apples to eat meanwhile
+#[derive(Clone)]
+struct Level {
+    map:String,
+    goal:Goal,
+    stars:[u32;2]
+}
+
+enum Outcome {
+    Cleared(u8), //stars
+    Failed,
+    Quit
+}
+
+impl Goal {
+    fn describe(&self) -> String {
+        match self {
+            Goal::Apples(apples) => format!("eat {} apples",apples),
+            Goal::Survive(time) => format!("survive {} s",time),
+            Goal::Collect(fruits) => format!("collect all {} fruit",fruits)
+        }
+    }
+
+    fn progress(&self,game:&Game) -> String {
+        let score = game.snakes[0].score;
+        match self {
+            Goal::Apples(apples) | Goal::Collect(apples) => format!("{}/{}",score,apples),
+            Goal::Survive(time) => format!("{}/{} s",game_seconds(game),time)
+        }
+    }
+
+    //a map without enough free cells places less fruit than asked, only what was placed can be collected
+    fn capped(self,game:&Game) -> Goal {
+        match self {
+            Goal::Collect(fruits) => Goal::Collect(fruits.min(1 + game.leftovers.len() as u16)),
+            goal => goal
+        }
+    }
+
+    fn reached(&self,game:&Game) -> bool {
+        match *self {
+            Goal::Apples(apples) | Goal::Collect(apples) => game.snakes[0].score >= apples,
+            Goal::Survive(time) => game_seconds(game) >= time
+        }
+    }
+}
+
+impl Level {
+    fn parse(line:&str) -> Option<Level> {
+        let words = line.split_whitespace().collect::<Vec<&str>>();
+        let [map,goal,target,two,three] = words[..] else {return None};
+        let goal = match goal {
+            "apples" => Goal::Apples(target.parse().ok()?),
+            "survive" => Goal::Survive(target.parse().ok()?),
+            "collect" => Goal::Collect(target.parse().ok()?),
+            _ => return None
+        };
+        Some(Level {map:map.to_string(),goal,stars:[two.parse().ok()?,three.parse().ok()?]})
+    }
+
+    fn path(&self) -> String {
+        format!("{}/{}.txt",CAMPAIGN_DIR,self.map)
+    }
+
+    //one for clearing it, the rest for doing it fast (or eating a lot while surviving)
+    fn stars(&self,game:&Game) -> u8 {
+        let better = |limit:u32| match self.goal {
+            Goal::Survive(_) => game.snakes[0].score as u32 >= limit,
+            _ => game_seconds(game) <= limit
+        };
+        1 + self.stars.iter().filter(|limit| better(**limit)).count() as u8
+    }
+
+    fn star_hints(&self,theme:&Theme) -> [String;2] {
+        let hint = |stars:u8,limit:u32| match self.goal {
+            Goal::Survive(_) => format!("{} {} apples",stars_text(stars,false,theme),limit),
+            _ => format!("{} in {} s",stars_text(stars,false,theme),limit)
+        };
+        [hint(2,self.stars[0]),hint(3,self.stars[1])]
+    }
+}
+
+//empty ones are shown too, unless they're only for the hint
+fn stars_text(stars:u8,empty:bool,theme:&Theme) -> String {
+    let missing = if empty {MAX_STARS - stars} else {0};
+    theme.text(&("★".repeat(stars as usize) + &"☆".repeat(missing as usize)))
+}
+
+fn load_levels() -> Vec<Level> {
+    let text = fs::read_to_string(format!("{}/{}",CAMPAIGN_DIR,FILE_LEVELS)).unwrap_or_default();
+    text.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(Level::parse)
+        .collect()
+}
+
+//best stars of every cleared level, by map name so the levels can be reordered
+struct Progress {
+    stars:Vec<(String,u8)>
+}
+
+impl Progress {
+    fn load() -> Progress {
+        let text = fs::read_to_string(FILE_PROGRESS).unwrap_or_default();
+        let stars = text.lines().filter_map(|line| {
+            let (map,stars) = line.split_once(' ')?;
+            Some((map.to_string(),stars.trim().parse::<u8>().ok()?.min(MAX_STARS)))
+        }).collect();
+        Progress {stars}
+    }
+
+    fn save(&self) {
+        let text = self.stars.iter().map(|(map,stars)| format!("{} {}\n",map,stars)).collect::<String>();
+        write_atomically(FILE_PROGRESS,&text).ok(); //stars stay in memory, so the next cleared level tries to write them again
+    }
+
+    fn get(&self,map:&str) -> u8 {
+        self.stars.iter().find(|(name,_)| name == map).map_or(0,|(_,stars)| *stars)
+    }
+
+    fn record(&mut self,map:&str,stars:u8) {
+        match self.stars.iter_mut().find(|(name,_)| name == map) {
+            Some((_,best)) => *best = (*best).max(stars),
+            None => self.stars.push((map.to_string(),stars))
+        }
+        self.save();
+    }
+
+    //first level is always open, every next one needs the previous one cleared
+    fn unlocked(&self,levels:&[Level],level:usize) -> bool {
+        level == 0 || self.get(&levels[level - 1].map) > 0
+    }
+}
+
+fn draw_levels(levels:&[Level],progress:&Progress,selected:usize,theme:&Theme) {
+    let total = levels.iter().map(|level| progress.get(&level.map) as usize).sum::<usize>();
+    queue!(
+        stdout(),
+        MoveTo(LIST_X,GLOBAL_OFFSET_Y),
+        Print(format!("Campaign   {} {}/{}",stars_text(1,false,theme),total,levels.len() * MAX_STARS as usize))
+    ).handle();
+    for (row,level) in levels.iter().enumerate() {
+        let line = if progress.unlocked(levels,row) {
+            format!(
+                "{:>2}. {:<name$} {:<goal$} {}",
+                row + 1,level.map,level.goal.describe(),stars_text(progress.get(&level.map),true,theme),
+                name = NAME_WIDTH,goal = GOAL_WIDTH
+            )
+        } else {
+            format!("{:>2}. {:<name$} {:<goal$}",row + 1,"locked","",name = NAME_WIDTH,goal = GOAL_WIDTH + MAX_STARS as usize + 1)
+        };
+        let line = if row == selected {theme.highlight(line)} else if progress.unlocked(levels,row) {line.reset()} else {line.with(theme.locked)};
+        queue!(stdout(),MoveTo(LIST_X,LIST_Y + row as u16),Print(line)).handle();
+    }
+}
+
+//level map screen, the chosen level is played until cleared or given up
+pub fn run(game_field:&str,theme:&Theme) {
+    let levels = load_levels();
+    let mut progress = Progress::load();
+    if levels.is_empty() {
+        execute!(
+            stdout(),
+            Clear(ClearType::All),
+            MoveTo(LIST_X,GLOBAL_OFFSET_Y),
+            Print(format!("No levels in \"{}/{}\", press any key",CAMPAIGN_DIR,FILE_LEVELS).with(theme.bad))
+        ).handle();
+        return wait_for_start()
+    }
+    //the first level that isn't cleared yet
+    let mut selected = (0..levels.len()).rev().find(|level| progress.unlocked(&levels,*level)).unwrap_or(0);
+    loop {
+        execute!(stdout(),Clear(ClearType::All)).handle();
+        let mut list = List::new(levels.len(),selected);
+        list.draw_hints(HINTS,0);
+        let chosen = loop {
+            draw_levels(&levels,&progress,selected,theme);
+            stdout().flush().handle();
+            let input = list.input();
+            selected = list.selected;
+            match input {
+                InputResult::Click if progress.unlocked(&levels,selected) => break selected,
+                InputResult::Abort => return,
+                _ => ()
+            }
+        };
+        let mut level = chosen;
+        loop {
+            let outcome = play(game_field,level,&levels[level],theme);
+            let last = level + 1 == levels.len();
+            let again = match outcome {
+                Outcome::Cleared(stars) => {
+                    progress.record(&levels[level].map,stars);
+                    draw_box(
+                        "LEVEL CLEAR!".to_string().with(theme.good),
+                        &[stars_text(stars,true,theme),(if last {"ENTER - again"} else {"ENTER - next"}).to_string(),"ESC - levels".to_string()],
+                        theme
+                    );
+                    let again = confirm();
+                    if again && !last {level += 1}
+                    again
+                },
+                Outcome::Failed => {
+                    draw_box("LEVEL FAILED".to_string().with(theme.bad),&[String::new(),"ENTER - retry".to_string(),"ESC - levels".to_string()],theme);
+                    confirm()
+                },
+                Outcome::Quit => false
+            };
+            if !again {break}
+        }
+        selected = level;
+    }
+}
+
+fn draw_goal(number:usize,level:&Level,theme:&Theme) {
+    let [two,three] = level.star_hints(theme);
+    let lines = [
+        format!("Level {}: {}",number + 1,level.map),
+        format!("Goal: {}",level.goal.describe()),
+        two,
+        three
+    ];
+    for (row,line) in (0u16..).zip(lines) {
+        queue!(stdout(),MoveTo(GAME_INFO_X,GOAL_OFFSET_Y + row),Print(line)).handle();
+    }
+}
+
+fn draw_progress(level:&Level,game:&Game) {
+    queue!(
+        stdout(),
+        MoveTo(GAME_INFO_X,GOAL_OFFSET_Y + 5),
+        Clear(ClearType::UntilNewLine),
+        Print(format!("Progress: {}",level.goal.progress(game))),
+        MoveTo(GAME_TIME_OFFSET,GLOBAL_OFFSET_Y),
+        Print(game_seconds(game))
+    ).handle();
+}
+
+fn play(game_field:&str,number:usize,level:&Level,theme:&Theme) -> Outcome {
+    let map = Map::load(&level.path());
+    let fruits = if let Goal::Collect(fruits) = level.goal {fruits} else {0};
+    let mut game = start_game(game_field,&map,1,Rules {fruits,..Rules::default()},theme);
+    let level = &Level {goal:level.goal.capped(&game),..level.clone()};
+    draw_goal(number,level,theme);
+    draw_progress(level,&game);
+    stdout().flush().handle();
+    wait_for_start();
+    execute!(stdout(),MoveTo(GAME_INFO_X,3),Clear(ClearType::UntilNewLine)).handle();
+    let mut tick = SystemTime::now();
+    loop {
+        let elapsed = tick.elapsed().unwrap().as_millis();
+        let wait = Duration::from_millis(GAME_TICK.saturating_sub(elapsed as u64));
+        if let InputResult::Abort = game_input(&mut game.snakes[..1],wait) {return Outcome::Quit}
+        if elapsed < GAME_TICK as u128 {continue}
+        tick = SystemTime::now();
+        let step = game.step(&[None]);
+        draw_step(&game,&step,theme);
+        draw_progress(level,&game);
+        stdout().flush().handle();
+        if !game.snakes[0].alive {return Outcome::Failed}
+        if level.goal.reached(&game) {return Outcome::Cleared(level.stars(&game))}
+    }
+}
@@ -0,0 +1,240 @@
+use std::{
+    io::{Write,stdout},
+    time::{SystemTime,UNIX_EPOCH,Duration}
+};
+
+use crossterm::{
+    execute, queue,
+    terminal::{Clear,ClearType},
+    cursor::MoveTo,
+    style::{Print,Stylize}
+};
+
+use crate::{
+    GAME_TICK,
+    GAME_TIME_OFFSET,
+    GAME_FIELD_SIZE,
+    GAME_FIELD_OFFSET_Y,
+    GAME_INFO_X,
+    GLOBAL_OFFSET_X,
+    GLOBAL_OFFSET_Y,
+    FILE_DAILY_EXPORT,
+    NONE,
+    option,
+    start_seeded_game,
+    draw_step,
+    draw_map,
+    draw_box,
+    wait_for_start,
+    confirm,
+    game_seconds,
+    general::{error_handling::*,graphics::*,input::*},
+    engine::{Game,GameFunctionality,Rules},
+    map::{Map,MapFunctionality,Spawn,write_atomically},
+    generator::{generate,STYLES},
+    net::{clean_name,default_name},
+    external::csv_field,
+    scores::{Scores,SCORES_KEPT},
+    ghost::{Ghost,Run},
+    theme::Theme
+};
+
+const SEED_MIX:u64 = 0x9E37_79B9_7F4A_7C15; //days go one after another, their seeds shouldn't
+const DENSITY:(f64,f64) = (0.3,0.6); //daily maps are somewhere between
+const SECONDS_IN_DAY:u64 = 24 * 60 * 60;
+const TABLE_PREFIX:&str = "daily-";
+const INFO_X:u16 = GLOBAL_OFFSET_X;
+const INFO_Y:u16 = GLOBAL_OFFSET_Y + 2;
+const PREVIEW_Y:u16 = INFO_Y + 2;
+const TABLE_X:u16 = INFO_X + GAME_FIELD_SIZE as u16 + 2; //right of the map
+const HINTS_Y:u16 = PREVIEW_Y + SCORES_KEPT as u16 + 3;
+const HINTS:&str = "ENTER - play\nE - export scores\nESC - back";
+const GAME_INFO_Y:u16 = GAME_FIELD_OFFSET_Y + 2;
+
+//everything about today's game comes from the date, so everybody gets the same one
+struct Daily {
+    date:String,
+    seed:u64,
+    style:&'static str,
+    density:f64,
+    map:Map
+}
+
+impl Daily {
+    //days are counted in UTC, so the day changes at the same moment for everybody
+    fn today() -> Daily {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        Daily::of(now.as_secs() / SECONDS_IN_DAY)
+    }
+
+    fn of(days:u64) -> Daily {
+        let seed = (days + 1).wrapping_mul(SEED_MIX);
+        let seed = seed ^ (seed >> 29);
+        let style = STYLES[(seed % STYLES.len() as u64) as usize];
+        let density = DENSITY.0 + ((seed >> 16) % 100) as f64 / 100.0 * (DENSITY.1 - DENSITY.0);
+        let map = generate(style,seed,density,Spawn::new()).unwrap_or_else(Map::new); //every style in STYLES is there
+        Daily {date:date(days),seed,style,density,map}
+    }
+
+    fn table(&self) -> String {
+        format!("{}{}",TABLE_PREFIX,self.date)
+    }
+}
+
+//yyyy-mm-dd of the day that many days after 1970-01-01
+fn date(days:u64) -> String {
+    let days = days as i64 + 719468; //from 0000-03-01, so leap day is the last one of the year
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era/1460 + day_of_era/36524 - day_of_era/146096) / 365;
+    let day_of_year = day_of_era - (365*year_of_era + year_of_era/4 - year_of_era/100);
+    let month = (5*day_of_year + 2) / 153; //0 is March
+    let day = day_of_year - (153*month + 2)/5 + 1;
+    let month = if month < 10 {month + 3} else {month - 9};
+    let year = year_of_era + era*400 + if month <= 2 {1} else {0};
+    format!("{:04}-{:02}-{:02}",year,month,day)
+}
+
+//scores are compared by names, --name is the same one network game uses
+fn player_name() -> String {
+    option("--name")
+        .or(std::env::var("USER").ok())
+        .or(std::env::var("USERNAME").ok())
+        .and_then(|name| clean_name(&name))
+        .unwrap_or(default_name(0))
+}
+
+//every daily score there is, one line per score
+fn export(scores:&Scores) -> String {
+    let rows = scores.rows(TABLE_PREFIX);
+    let mut text = String::from("date,score,player\n");
+    for (table,score,player) in &rows {
+        text += &format!("{},{},{}\n",table.trim_start_matches(TABLE_PREFIX),score,csv_field(player));
+    }
+    match write_atomically(FILE_DAILY_EXPORT,&text) {
+        Ok(_) => format!("{} scores exported to \"{}\"",rows.len(),FILE_DAILY_EXPORT),
+        Err(error) => format!("Can't write \"{}\": {}",FILE_DAILY_EXPORT,error)
+    }
+}
+
+fn draw_daily(daily:&Daily,scores:&Scores,theme:&Theme) {
+    queue!(
+        stdout(),
+        MoveTo(INFO_X,GLOBAL_OFFSET_Y),
+        Print(format!("Daily challenge {}",daily.date)),
+        MoveTo(INFO_X,INFO_Y),
+        Print(format!("{} map, density {:.2}, seed {}",daily.style,daily.density,daily.seed))
+    ).handle();
+    draw_map(&daily.map.walls,INFO_X,PREVIEW_Y,theme);
+    let table = scores.table(&daily.table());
+    queue!(stdout(),MoveTo(TABLE_X,PREVIEW_Y),Print("Today's best:")).handle();
+    for place in 0..SCORES_KEPT {
+        let line = match table.get(place) {
+            Some((score,player)) => format!("{}. {:<4} {}",place + 1,score,player),
+            None => format!("{}. -",place + 1)
+        };
+        queue!(stdout(),MoveTo(TABLE_X,PREVIEW_Y + 1 + place as u16),Clear(ClearType::UntilNewLine),Print(line)).handle();
+    }
+    draw_simple_ascii_picture(HINTS,TABLE_X,HINTS_Y);
+}
+
+//today's map and best scores, the game can be played as many times as you want
+pub fn run(game_field:&str,theme:&Theme) {
+    let daily = Daily::today();
+    let mut scores = Scores::load();
+    let player = player_name();
+    let mut status = String::new();
+    loop {
+        execute!(stdout(),Clear(ClearType::All)).handle();
+        draw_daily(&daily,&scores,theme);
+        queue!(stdout(),MoveTo(TABLE_X,HINTS_Y + 4),Print(&status)).handle();
+        stdout().flush().handle();
+        let mut cursor = Cursor {x:INFO_X,y:INFO_Y,hover:NONE}; //where the screen starts, so arrows have room to move it
+        match cursor_input(&mut cursor,Duration::MAX) {
+            InputResult::Click => (),
+            InputResult::Key('e' | 'E') => {
+                status = export(&scores);
+                continue
+            },
+            InputResult::Abort => return,
+            _ => continue
+        }
+        status.clear();
+        while let Some(score) = play(game_field,&daily,&scores,&player,theme) {
+            let best = score > 0 && scores.add(&daily.table(),score,&player,false);
+            let result = format!("score {}{}",score,if best {" best!"} else {""});
+            draw_box("GAME OVER!".to_string().with(theme.bad),&[result,"ENTER - again".to_string(),"ESC - back".to_string()],theme);
+            if !confirm() {break}
+        }
+    }
+}
+
+//score, None if the game was left before the snake died
+fn play(game_field:&str,daily:&Daily,scores:&Scores,player:&str,theme:&Theme) -> Option<u32> {
+    let mut game = start_seeded_game(game_field,&daily.map,1,Rules::default(),daily.seed,theme);
+    let mut ghost = Ghost::load(&daily.table());
+    let mut run = Run::start(&game,player);
+    let best = scores.table(&daily.table()).first().map_or("-".to_string(),|(score,_)| score.to_string());
+    let lines = [format!("Daily {}",daily.date),format!("Best today: {}",best)];
+    for (row,line) in (0u16..).zip(lines) {
+        queue!(stdout(),MoveTo(GAME_INFO_X,GAME_INFO_Y + row),Print(line)).handle();
+    }
+    draw_ghost(&mut ghost,&game,theme);
+    stdout().flush().handle();
+    wait_for_start();
+    execute!(stdout(),MoveTo(GAME_INFO_X,3),Clear(ClearType::UntilNewLine)).handle();
+    let mut tick = SystemTime::now();
+    loop {
+        let elapsed = tick.elapsed().unwrap().as_millis();
+        let wait = Duration::from_millis(GAME_TICK.saturating_sub(elapsed as u64));
+        if let InputResult::Abort = game_input(&mut game.snakes[..1],wait) {return None}
+        if elapsed < GAME_TICK as u128 {continue}
+        tick = SystemTime::now();
+        let step = game.step(&[None]);
+        draw_step(&game,&step,theme);
+        draw_ghost(&mut ghost,&game,theme);
+        run.step(&game);
+        queue!(stdout(),MoveTo(GAME_TIME_OFFSET,GLOBAL_OFFSET_Y),Print(game_seconds(&game))).handle();
+        stdout().flush().handle();
+        if !game.snakes[0].alive {
+            let score = game.snakes[0].score;
+            if score > 0 && ghost.is_none_or(|ghost| score > ghost.score()) {
+                run.save(&daily.table());
+            }
+            return Some(score as u32)
+        }
+    }
+}
+
+//the best run of today, and how many apples it had by now
+fn draw_ghost(ghost:&mut Option<Ghost>,game:&Game,theme:&Theme) {
+    let Some(ghost) = ghost else {return};
+    ghost.draw(game,theme);
+    queue!(
+        stdout(),
+        MoveTo(GAME_INFO_X,GAME_INFO_Y + 2),
+        Clear(ClearType::UntilNewLine),
+        Print(format!("Ghost: {}",ghost.score_at(game.tick)).with(theme.ghost))
+    ).handle();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        let days = [(0,"1970-01-01"),(59,"1970-03-01"),(10956,"1999-12-31"),(11016,"2000-02-29"),(11017,"2000-03-01"),(47541,"2100-03-01"),(20745,"2026-10-19")];
+        for (days,expected) in days {
+            assert_eq!(date(days),expected);
+        }
+    }
+
+    #[test]
+    fn same_day_same_map() {
+        let (first,again,next) = (Daily::of(20745),Daily::of(20745),Daily::of(20746));
+        assert!(first.seed == again.seed && first.map.walls == again.map.walls && first.table() == again.table());
+        assert!(first.seed != next.seed && first.table() != next.table());
+        assert!((DENSITY.0..=DENSITY.1).contains(&first.density));
+    }
+}
@@ -0,0 +1,449 @@
+use std::collections::VecDeque;
+use rand::{rngs::StdRng,SeedableRng};
+
+use crate::{
+    Pos,
+    Direction,
+    DirectionFunctionality,
+    Snake,
+    SnakeFunctionality,
+    Fruit,
+    FruitFunctionality,
+    next_pos,
+    GAME_FIELD_SIZE,
+    map::{Map,Walls,wall_ring},
+    obstacles::{Obstacle,ObstacleFunctionality},
+    bots::GameView
+};
+
+const PLAYER_SEED_MIX:u64 = 0x9E37_79B9_7F4A_7C15; //so rivals of one game don't roll the same dice
+
+//the whole game without drawing anything, so bots can play it as fast as they can
+pub struct Game {
+    pub walls:Walls,
+    pub snakes:Vec<Snake>,
+    pub fruit:Fruit,
+    pub leftovers:Vec<Pos>, //fruit made of dead snakes, doesn't come back after eating
+    pub sequence:VecDeque<Pos>, //where the fruit goes next, from the map file
+    pub rules:Rules,
+    ring:usize, //next ring of the field to become walls
+    pub obstacles:Vec<Obstacle>,
+    pub tick:u32,
+    leaving:Vec<usize>, //players who left the game, they die on the next step
+    pub seed:u64, //what the game started from, bots that roll dice start from it too
+    rng:StdRng
+}
+
+//things that change how the game goes, defaults are the classic single game
+#[derive(Clone,Copy)]
+pub struct Rules {
+    pub corpses:Corpses,
+    pub shrink:u32, //ticks between field getting smaller by one ring, 0 = it never does
+    pub fruits:u16, //that many fruit are scattered at the start and don't come back, 0 = one fruit that always does
+    pub grow:u32, //ticks between snakes getting longer by themselves, 0 = only fruit makes them longer
+    pub zen:bool //snakes go through themselves, walls and obstacles still kill
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules {corpses:Corpses::Stay,shrink:0,fruits:0,grow:0,zen:false}
+    }
+}
+
+//what happens to snake's body when it dies
+#[derive(Clone,Copy,PartialEq)]
+pub enum Corpses {
+    Stay,
+    Vanish,
+    Fruit
+}
+
+#[derive(Clone,Copy,PartialEq)]
+pub enum DeathCause {
+    Wall,
+    Snake, //bit itself
+    Rival, //bit another snake
+    HeadOn, //two heads in one cell
+    Obstacle,
+    Left //player is gone, network game only
+}
+
+//what happened during the tick, so screen knows what to redraw, one entry per snake
+pub struct Step {
+    pub tails:Vec<Option<Pos>>, //cell tail has left
+    pub prev_moves:Vec<Direction>, //needed to draw the neck
+    pub ate:Vec<bool>,
+    pub deaths:Vec<Option<DeathCause>>,
+    pub obstacle_cells:Vec<Pos>, //cells obstacles were in before they moved
+    pub removed:Vec<Pos>, //bodies of snakes that died and vanished
+    pub walls_added:Vec<Pos> //border moved in
+}
+
+pub trait GameFunctionality {
+    fn new(map:&Map,seed:u64,players:usize) -> Self;
+    fn step(&mut self,inputs:&[Option<Direction>]) -> Step;
+    fn alive(&self) -> usize;
+    fn leave(&mut self,player:usize);
+    fn scatter(&mut self);
+    fn view(&self,player:usize) -> GameView<'_>;
+}
+
+impl GameFunctionality for Game {
+    fn new(map:&Map,seed:u64,players:usize) -> Game {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut walls = map.walls;
+        let snakes = (0..players).map(|player| Snake::player(&map.spawn,player)).collect::<Vec<Snake>>();
+        for snake in &snakes { //nobody should die before the game starts
+            for part in snake.body.iter().chain([&snake.pos]) {
+                walls[part.y as usize][part.x as usize] = false;
+            }
+        }
+        let mut sequence = map.fruits.iter().copied().collect::<VecDeque<Pos>>();
+        let fruit = match sequence.pop_front() {
+            Some(pos) => Fruit {pos},
+            None => Fruit::new(&walls,&snakes,&map.obstacles,&mut rng)
+        };
+        Game {
+            walls,
+            snakes,
+            fruit,
+            leftovers:vec![],
+            sequence,
+            rules:Rules::default(),
+            ring:0,
+            obstacles:map.obstacles.clone(),
+            tick:0,
+            leaving:vec![],
+            seed,
+            rng
+        }
+    }
+
+    fn step(&mut self,inputs:&[Option<Direction>]) -> Step {
+        let count = self.snakes.len();
+        let mut step = Step {
+            tails:vec![None;count],
+            prev_moves:self.snakes.iter().map(|s| s.prev_move.copy()).collect(),
+            ate:vec![false;count],
+            deaths:vec![None;count],
+            obstacle_cells:vec![],
+            removed:vec![],
+            walls_added:vec![]
+        };
+        let mut fruit_eaten = false;
+        let grow = self.rules.grow > 0 && (self.tick + 1) % self.rules.grow == 0;
+        //everybody moves at the same time
+        for (i,snake) in self.snakes.iter_mut().enumerate() {
+            if !snake.alive || self.leaving.contains(&i) {continue}
+            if let Some(Some(dir)) = inputs.get(i) {
+                snake.last_input = dir.copy();
+            }
+            //makes much easier to turn 180 degrees
+            if !snake.last_input.is_opposite_of(&snake.prev_move) {
+                snake.direction = snake.last_input.copy()
+            };
+            snake.body.push_back(snake.pos);
+            snake.pos = next_pos(&snake.pos,&snake.direction);
+            snake.prev_move = snake.direction.copy();
+            let leftover = self.leftovers.iter().position(|cell| *cell == snake.pos);
+            if snake.pos == self.fruit.pos || leftover.is_some() {
+                snake.score += 1;
+                step.ate[i] = true;
+                fruit_eaten |= snake.pos == self.fruit.pos;
+                if let Some(leftover) = leftover {
+                    self.leftovers.remove(leftover);
+                }
+            } else if !grow {
+                step.tails[i] = snake.body.pop_front();
+            }
+        }
+        //rings that are walls already don't count, so the border moves every time
+        if self.rules.shrink > 0 && (self.tick + 1) % self.rules.shrink == 0 {
+            while step.walls_added.is_empty() && self.ring < GAME_FIELD_SIZE/2 {
+                step.walls_added = wall_ring(&mut self.walls,self.ring);
+                self.ring += 1;
+            }
+            let walls = &self.walls;
+            self.leftovers.retain(|cell| !walls[cell.y as usize][cell.x as usize]);
+            if walls[self.fruit.pos.y as usize][self.fruit.pos.x as usize] {
+                fruit_eaten = true;
+            }
+        }
+        let next = if fruit_eaten {self.sequence.pop_front()} else {None};
+        if let Some(next) = next {
+            self.fruit.pos = next;
+        } else if fruit_eaten && self.rules.fruits == 0 {
+            self.fruit.respawn(&self.walls,&self.snakes,&self.obstacles,&mut self.rng);
+        } else if fruit_eaten && !self.leftovers.is_empty() { //the last one stays under the head
+            self.fruit.pos = self.leftovers.remove(0);
+        }
+
+        //obstacles move after snakes, so snake can escape them by one tick
+        step.obstacle_cells = self.obstacles.iter().flat_map(|o| o.cells()).collect();
+        for obstacle in &mut self.obstacles {
+            obstacle.advance(&self.walls);
+        }
+        //covers the case when snake and obstacle went through each other too,
+        //because old head position is a part of the body now
+        for (i,snake) in self.snakes.iter().enumerate() {
+            if !snake.alive {continue}
+            let rivals = self.snakes.iter().enumerate()
+                .filter(|(j,rival)| *j != i && rival.alive)
+                .map(|(_,rival)| rival);
+            //border can come down right on the body
+            let in_wall = |cell:&Pos| self.walls[cell.y as usize][cell.x as usize];
+            step.deaths[i] = if self.leaving.contains(&i) {
+                Some(DeathCause::Left)
+            } else if in_wall(&snake.pos) || step.walls_added.iter().any(|cell| snake.body.contains(cell)) {
+                Some(DeathCause::Wall)
+            } else if self.obstacles.iter().any(
+                |o| o.cells().iter().any(|cell| snake.is_in_point(cell.x,cell.y))
+            ) {
+                Some(DeathCause::Obstacle)
+            } else if snake.body.contains(&snake.pos) && !self.rules.zen {
+                Some(DeathCause::Snake)
+            } else if rivals.clone().any(|rival| rival.pos == snake.pos) {
+                Some(DeathCause::HeadOn)
+            } else if rivals.clone().any(|rival| rival.body.contains(&snake.pos)) {
+                Some(DeathCause::Rival)
+            } else {None};
+        }
+        for (snake,death) in self.snakes.iter_mut().zip(&step.deaths) {
+            if death.is_none() {continue}
+            snake.alive = false;
+            //head stays where it crashed, there is something else in that cell anyway
+            if self.rules.corpses != Corpses::Stay {
+                let body = snake.body.drain(..).collect::<Vec<Pos>>();
+                if self.rules.corpses == Corpses::Fruit {
+                    self.leftovers.extend(body.iter().filter(|cell| !self.walls[cell.y as usize][cell.x as usize]));
+                }
+                step.removed.extend(body);
+            }
+        }
+        self.leaving.clear();
+        self.tick += 1;
+        step
+    }
+
+    fn alive(&self) -> usize {
+        self.snakes.iter().filter(|s| s.alive).count()
+    }
+
+    //snake stops where it is and dies on the next step like any other snake
+    fn leave(&mut self,player:usize) {
+        self.leaving.push(player);
+    }
+
+    //the rest of Rules.fruits, the real fruit is the first of them
+    fn scatter(&mut self) {
+        let mut taken = self.walls;
+        taken[self.fruit.pos.y as usize][self.fruit.pos.x as usize] = true;
+        for _ in 1..self.rules.fruits {
+            let fruit = Fruit::new(&taken,&self.snakes,&self.obstacles,&mut self.rng);
+            if taken[fruit.pos.y as usize][fruit.pos.x as usize] {break} //no free cells left
+            taken[fruit.pos.y as usize][fruit.pos.x as usize] = true;
+            self.leftovers.push(fruit.pos);
+        }
+    }
+
+    fn view(&self,player:usize) -> GameView<'_> {
+        let snake = &self.snakes[player];
+        GameView {
+            walls:&self.walls,
+            snake,
+            rivals:self.snakes.iter().enumerate()
+                .filter(|(i,rival)| *i != player && rival.alive)
+                .map(|(_,rival)| rival)
+                .collect(),
+            fruit:&self.fruit,
+            leftovers:&self.leftovers,
+            obstacles:&self.obstacles,
+            score:snake.score,
+            tick:self.tick,
+            seed:self.seed ^ (player as u64).wrapping_mul(PLAYER_SEED_MIX)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::{MapFunctionality,Spawn};
+
+    //empty field with the border, snake looks right from the middle
+    fn start(fruits:&[Pos]) -> Game {
+        let mut map = Map::new();
+        map.spawn = Spawn {pos:Pos {x:8,y:10},direction:Direction::Right};
+        map.fruits = fruits.to_vec();
+        Game::new(&map,1,1)
+    }
+
+    fn cells(snake:&Snake) -> Vec<Pos> {
+        snake.body.iter().copied().chain([snake.pos]).collect()
+    }
+
+    #[test]
+    fn moves_and_grows() {
+        let mut game = start(&[Pos {x:10,y:10}]);
+        let step = game.step(&[None]);
+        assert!(!step.ate[0] && step.tails[0] == Some(Pos {x:6,y:10}));
+        assert!(cells(&game.snakes[0]) == [Pos {x:7,y:10},Pos {x:8,y:10},Pos {x:9,y:10}]);
+        let step = game.step(&[None]);
+        assert!(step.ate[0] && step.tails[0].is_none());
+        assert_eq!(game.snakes[0].score,1);
+        assert_eq!(game.snakes[0].body.len(),3);
+        assert!(game.fruit.pos != game.snakes[0].pos); //new one went somewhere else
+        assert_eq!(game.tick,2);
+    }
+
+    #[test]
+    fn grows_by_itself() {
+        let mut game = start(&[]);
+        game.rules.grow = 2;
+        game.fruit.pos = Pos {x:3,y:3};
+        game.step(&[None]);
+        assert_eq!(game.snakes[0].body.len(),2);
+        let step = game.step(&[None]);
+        assert!(step.tails[0].is_none());
+        assert_eq!(game.snakes[0].body.len(),3);
+    }
+
+    #[test]
+    fn turns_but_not_back() {
+        let mut game = start(&[]);
+        game.step(&[Some(Direction::Left)]); //straight into the neck, ignored
+        assert!(game.snakes[0].pos == Pos {x:9,y:10});
+        game.step(&[Some(Direction::Up)]);
+        assert!(game.snakes[0].pos == Pos {x:9,y:9} && game.snakes[0].alive);
+    }
+
+    #[test]
+    fn deaths() {
+        let mut game = start(&[]);
+        for _ in 0..10 {
+            game.step(&[None]);
+        }
+        let step = game.step(&[None]);
+        assert!(step.deaths[0] == Some(DeathCause::Wall) && !game.snakes[0].alive);
+        assert_eq!(game.alive(),0);
+
+        //long snake turning into itself
+        let mut game = start(&[]);
+        let snake = &mut game.snakes[0];
+        snake.pos = Pos {x:5,y:5};
+        snake.body = VecDeque::from([Pos {x:6,y:4},Pos {x:6,y:5},Pos {x:6,y:6},Pos {x:5,y:6}]);
+        (snake.direction,snake.prev_move,snake.last_input) = (Direction::Up,Direction::Up,Direction::Up);
+        let step = game.step(&[Some(Direction::Right)]);
+        assert!(step.deaths[0] == Some(DeathCause::Snake));
+    }
+
+    #[test]
+    fn zen() {
+        //the same turn into itself as in deaths, but nothing happens
+        let mut game = start(&[]);
+        game.rules.zen = true;
+        let snake = &mut game.snakes[0];
+        snake.pos = Pos {x:5,y:5};
+        snake.body = VecDeque::from([Pos {x:6,y:4},Pos {x:6,y:5},Pos {x:6,y:6},Pos {x:5,y:6}]);
+        (snake.direction,snake.prev_move,snake.last_input) = (Direction::Up,Direction::Up,Direction::Up);
+        let step = game.step(&[Some(Direction::Right)]);
+        assert!(step.deaths[0].is_none() && game.snakes[0].pos == Pos {x:6,y:5});
+
+        //the border is still a wall, the snake doesn't walk through it
+        let mut game = start(&[]);
+        game.rules.zen = true;
+        for _ in 0..10 {
+            game.step(&[None]);
+        }
+        let step = game.step(&[None]);
+        assert!(step.deaths[0] == Some(DeathCause::Wall) && !game.snakes[0].alive);
+    }
+
+    #[test]
+    fn rivals() {
+        let mut game = start(&[]);
+        game.snakes = vec![
+            Snake::new(Pos {x:5,y:5},Direction::Right),
+            Snake::new(Pos {x:7,y:5},Direction::Left),
+            Snake::new(Pos {x:10,y:10},Direction::Right),
+            Snake::new(Pos {x:11,y:9},Direction::Up) //neck is right where the third one goes
+        ];
+        let step = game.step(&[None,None,None,None]);
+        assert!(step.deaths[0] == Some(DeathCause::HeadOn) && step.deaths[1] == Some(DeathCause::HeadOn));
+        assert!(step.deaths[2] == Some(DeathCause::Rival) && step.deaths[3].is_none());
+        assert_eq!(game.alive(),1);
+    }
+
+    #[test]
+    fn leaving() {
+        let mut game = start(&[]);
+        game.rules.corpses = Corpses::Vanish;
+        game.snakes.push(Snake::new(Pos {x:10,y:5},Direction::Right));
+        game.leave(1);
+        let step = game.step(&[None,None]);
+        assert!(step.deaths[0].is_none() && step.deaths[1] == Some(DeathCause::Left));
+        //didn't move, body went away like with any other death
+        assert!(step.tails[1].is_none() && step.removed == [Pos {x:8,y:5},Pos {x:9,y:5}]);
+        assert!(!game.snakes[1].alive && game.alive() == 1);
+        game.step(&[None,None]);
+        assert!(game.snakes[0].alive);
+    }
+
+    #[test]
+    fn corpses() {
+        for corpses in [Corpses::Stay,Corpses::Vanish,Corpses::Fruit] {
+            let mut game = start(&[]);
+            game.rules.corpses = corpses;
+            game.snakes[0].pos = Pos {x:18,y:10};
+            game.snakes[0].body = VecDeque::from([Pos {x:16,y:10},Pos {x:17,y:10}]);
+            let step = game.step(&[None]);
+            assert!(step.deaths[0] == Some(DeathCause::Wall));
+            let body = [Pos {x:17,y:10},Pos {x:18,y:10}];
+            match corpses {
+                Corpses::Stay => assert!(game.snakes[0].body == body && step.removed.is_empty()),
+                Corpses::Vanish => assert!(game.snakes[0].body.is_empty() && step.removed == body && game.leftovers.is_empty()),
+                Corpses::Fruit => assert!(step.removed == body && game.leftovers == body)
+            }
+        }
+    }
+
+    #[test]
+    fn shrinks() {
+        let mut game = start(&[]);
+        game.rules.shrink = 2;
+        game.fruit.pos = Pos {x:1,y:5}; //ring 1 takes it
+        game.step(&[None]);
+        assert!(!game.walls[1][5]);
+        let step = game.step(&[None]);
+        //border was a wall already, so it's the next ring that comes
+        assert!(game.walls[1][5] && game.walls[18][18] && !game.walls[2][5]);
+        assert_eq!(step.walls_added.len(),4*17);
+        assert!(game.fruit.pos != Pos {x:1,y:5} && !game.walls[game.fruit.pos.y as usize][game.fruit.pos.x as usize]);
+        assert!(game.snakes[0].alive);
+
+        //snake on the ring that comes down dies with it
+        let mut game = start(&[]);
+        game.rules.shrink = 1;
+        game.snakes[0].pos = Pos {x:5,y:1};
+        game.snakes[0].body = VecDeque::from([Pos {x:3,y:1},Pos {x:4,y:1}]);
+        let step = game.step(&[None]);
+        assert!(step.deaths[0] == Some(DeathCause::Wall));
+    }
+
+    #[test]
+    fn same_seed_same_game() {
+        let play = || {
+            let mut game = start(&[]);
+            let mut fruits = vec![];
+            for tick in 0..40 {
+                let turn = [Direction::Up,Direction::Right,Direction::Down,Direction::Right][tick/3 % 4].copy();
+                game.step(&[Some(turn)]);
+                fruits.push(game.fruit.pos);
+            }
+            fruits
+        };
+        assert!(play() == play());
+        let game = Game::new(&Map::new(),3,2);
+        assert!(game.view(0).seed == 3 && game.view(1).seed != 3);
+    }
+}
@@ -0,0 +1,368 @@
+use std::{
+    io::{Write,BufRead,BufReader},
+    process::{Command,Child,Stdio},
+    sync::mpsc::{channel,sync_channel,Receiver,SyncSender,TrySendError},
+    thread,
+    time::{Duration,Instant}
+};
+
+use crate::{
+    Pos,
+    Direction,
+    bots::{Controller,GameView},
+    obstacles::ObstacleFunctionality
+};
+
+const JSON_DEPTH:usize = 64; //lists and objects inside each other, replies need 1
+
+//bot that lives in another process and talks with the game in json lines:
+//game sends state every tick, bot answers with {"tick":3,"direction":"up"} (or just up)
+pub struct ExternalBot {
+    process:Child,
+    states:SyncSender<String>,
+    replies:Receiver<String>,
+    timeout:Duration
+}
+
+//words of the command, "quoted words" or 'quoted words' can have spaces inside
+fn split_command(command:&str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word:Option<String> = None;
+    let mut quote = None;
+    for c in command.chars() {
+        match (quote,c) {
+            (Some(q),c) if c == q => quote = None,
+            (Some(_),c) => word.get_or_insert_with(String::new).push(c),
+            (None,'"' | '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new); //"" is an empty word, not nothing
+            },
+            (None,c) if c.is_whitespace() => words.extend(word.take()),
+            (None,c) => word.get_or_insert_with(String::new).push(c)
+        }
+    }
+    words.extend(word);
+    words
+}
+
+impl ExternalBot {
+    pub fn spawn(command:&str,timeout:Duration) -> std::io::Result<ExternalBot> {
+        let words = split_command(command);
+        let program = words.first().map(|word| word.as_str()).unwrap_or_default();
+        let mut process = Command::new(program)
+            .args(&words[1..words.len().max(1)])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null()) //anything it prints there would ruin the screen
+            .spawn()?;
+        let mut input = process.stdin.take().unwrap();
+        let output = process.stdout.take().unwrap();
+        //writing blocks too when bot doesn't read, the game shouldn't wait for it
+        //one state can wait here, if there's no room the bot is stuck and misses the tick
+        let (states,pending) = sync_channel::<String>(1);
+        thread::spawn(move || {
+            for state in pending {
+                if writeln!(input,"{}",state).and_then(|_| input.flush()).is_err() {break}
+            }
+        });
+        let (sender,replies) = channel();
+        thread::spawn(move || { //reading is blocking, so it gets its own thread
+            for line in BufReader::new(output).lines() {
+                match line {
+                    Ok(line) => if sender.send(line).is_err() {break},
+                    Err(_) => break
+                }
+            }
+        });
+        Ok(ExternalBot {process,states,replies,timeout})
+    }
+}
+
+impl Drop for ExternalBot {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+fn json_points<'a>(points:impl Iterator<Item = &'a Pos>) -> String {
+    points.map(|p| format!("[{},{}]",p.x,p.y)).collect::<Vec<String>>().join(",")
+}
+
+pub fn direction_name(direction:&Direction) -> &'static str {
+    match direction {
+        Direction::Up => "up",
+        Direction::Right => "right",
+        Direction::Left => "left",
+        Direction::Down => "down"
+    }
+}
+
+pub fn parse_direction(word:&str) -> Option<Direction> {
+    match word {
+        "up" => Some(Direction::Up),
+        "right" => Some(Direction::Right),
+        "left" => Some(Direction::Left),
+        "down" => Some(Direction::Down),
+        _ => None
+    }
+}
+
+//text as json string, quotes included
+pub fn json_string(text:&str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}",c as u32)),
+            c => json.push(c)
+        }
+    }
+    json.push('"');
+    json
+}
+
+//text as one csv field, quoted only when it has to be
+pub fn csv_field(text:&str) -> String {
+    if text.contains([',','"','\n','\r']) {
+        format!("\"{}\"",text.replace('"',"\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+pub fn state_json(view:&GameView) -> String {
+    let grid = view.walls.iter()
+        .map(|line| format!("\"{}\"",line.iter().map(|wall| if *wall {'1'} else {'0'}).collect::<String>()))
+        .collect::<Vec<String>>()
+        .join(",");
+    let snake = std::iter::once(&view.snake.pos).chain(view.snake.body.iter().rev()); //head goes first
+    let obstacles = view.obstacles.iter().flat_map(|o| o.cells()).collect::<Vec<Pos>>();
+    let rivals = view.rivals.iter()
+        .map(|rival| format!("[{}]",json_points(std::iter::once(&rival.pos).chain(rival.body.iter().rev()))))
+        .collect::<Vec<String>>()
+        .join(",");
+    format!(
+        "{{\"tick\":{},\"width\":{},\"height\":{},\"grid\":[{}],\"snake\":[{}],\"direction\":\"{}\",\"rivals\":[{}],\"fruits\":[{}],\"obstacles\":[{}],\"score\":{}}}",
+        view.tick,
+        view.walls[0].len(),
+        view.walls.len(),
+        grid,
+        json_points(snake),
+        direction_name(&view.snake.direction),
+        rivals,
+        json_points(std::iter::once(&view.fruit.pos).chain(view.leftovers)),
+        json_points(obstacles.iter()),
+        view.score
+    )
+}
+
+//just enough json to read what bots answer
+#[derive(Debug,PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Text(String),
+    List(Vec<Json>),
+    Object(Vec<(String,Json)>)
+}
+
+impl Json {
+    pub fn parse(text:&str) -> Option<Json> {
+        let mut chars = text.chars().peekable();
+        let value = Json::value(&mut chars,0)?;
+        Json::skip_spaces(&mut chars);
+        if chars.next().is_some() {return None} //something after the value
+        Some(value)
+    }
+
+    pub fn get(&self,key:&str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(name,_)| name == key).map(|(_,value)| value),
+            _ => None
+        }
+    }
+
+    fn skip_spaces(chars:&mut std::iter::Peekable<std::str::Chars>) {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    //`depth` is how many lists and objects it's in, a bot can't make the game run out of stack with [[[[...
+    fn value(chars:&mut std::iter::Peekable<std::str::Chars>,depth:usize) -> Option<Json> {
+        Json::skip_spaces(chars);
+        match *chars.peek()? {
+            '{' | '[' if depth >= JSON_DEPTH => None,
+            '{' => {
+                chars.next();
+                let mut fields = vec![];
+                Json::skip_spaces(chars);
+                if chars.next_if_eq(&'}').is_some() {return Some(Json::Object(fields))}
+                loop {
+                    Json::skip_spaces(chars);
+                    let Json::Text(key) = Json::value(chars,depth + 1)? else {return None};
+                    Json::skip_spaces(chars);
+                    chars.next_if_eq(&':')?;
+                    fields.push((key,Json::value(chars,depth + 1)?));
+                    Json::skip_spaces(chars);
+                    match chars.next()? {
+                        ',' => (),
+                        '}' => return Some(Json::Object(fields)),
+                        _ => return None
+                    }
+                }
+            },
+            '[' => {
+                chars.next();
+                let mut items = vec![];
+                Json::skip_spaces(chars);
+                if chars.next_if_eq(&']').is_some() {return Some(Json::List(items))}
+                loop {
+                    items.push(Json::value(chars,depth + 1)?);
+                    Json::skip_spaces(chars);
+                    match chars.next()? {
+                        ',' => (),
+                        ']' => return Some(Json::List(items)),
+                        _ => return None
+                    }
+                }
+            },
+            '"' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next()? {
+                        '"' => return Some(Json::Text(text)),
+                        '\\' => text.push(match chars.next()? {
+                            'n' => '\n',
+                            't' => '\t',
+                            'r' => '\r',
+                            'b' => '\u{8}',
+                            'f' => '\u{c}',
+                            'u' => {
+                                let code = (0..4).map(|_| chars.next()).collect::<Option<String>>()?;
+                                char::from_u32(u32::from_str_radix(&code,16).ok()?).unwrap_or('\u{fffd}')
+                            },
+                            c => c //\" \\ and \/
+                        }),
+                        c => text.push(c)
+                    }
+                }
+            },
+            c if c == '-' || c.is_ascii_digit() => {
+                let mut number = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || "+-.eE".contains(*c)) {
+                    number.push(c);
+                }
+                number.parse().ok().map(Json::Number)
+            },
+            _ => {
+                let word = std::iter::from_fn(|| chars.next_if(|c| c.is_ascii_alphabetic())).collect::<String>();
+                match word.as_str() {
+                    "true" => Some(Json::Bool(true)),
+                    "false" => Some(Json::Bool(false)),
+                    "null" => Some(Json::Null),
+                    _ => None
+                }
+            }
+        }
+    }
+}
+
+//{"tick":3,"direction":"up"} or just up, the tick is None when bot didn't say it
+fn parse_reply(reply:&str) -> Option<(Option<u32>,Direction)> {
+    let reply = reply.trim();
+    if let Some(direction) = parse_direction(&reply.to_lowercase()) {
+        return Some((None,direction))
+    }
+    let json = Json::parse(reply)?;
+    let direction = match json.get("direction")? {
+        Json::Text(direction) => parse_direction(&direction.to_lowercase())?,
+        _ => return None
+    };
+    let tick = match json.get("tick") {
+        Some(Json::Number(tick)) => Some(*tick as u32),
+        _ => None
+    };
+    Some((tick,direction))
+}
+
+impl Controller for ExternalBot {
+    fn decide(&mut self,view:&GameView) -> Option<Direction> {
+        while self.replies.try_recv().is_ok() {} //answers that came too late are useless now
+        match self.states.try_send(state_json(view)) {
+            Ok(()) => (),
+            Err(TrySendError::Full(_)) => return None, //bot didn't read the last ones yet
+            Err(TrySendError::Disconnected(_)) => return None //bot is dead, snake is on its own
+        }
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let reply = self.replies.recv_timeout(deadline.saturating_duration_since(Instant::now())).ok()?;
+            match parse_reply(&reply) {
+                Some((Some(tick),direction)) if tick == view.tick => return Some(direction),
+                Some((None,direction)) => return Some(direction),
+                _ => () //answer for some earlier tick or nonsense, maybe the right one is still coming
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_words() {
+        assert_eq!(split_command("python3 mybot.py"),["python3","mybot.py"]);
+        assert_eq!(split_command("  \"/my bots/run\" --name 'snake one' x\"y z\" "),["/my bots/run","--name","snake one","xy z"]);
+        assert_eq!(split_command("bot \"\""),["bot",""]);
+        assert!(split_command("   ").is_empty());
+    }
+
+    #[test]
+    fn csv() {
+        assert_eq!(csv_field("plain name"),"plain name");
+        assert_eq!(csv_field("maps/a,b.txt"),"\"maps/a,b.txt\"");
+        assert_eq!(csv_field("say \"hi\""),"\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"),"\"two\nlines\"");
+    }
+
+    #[test]
+    fn json() {
+        assert_eq!(
+            Json::parse(r#" {"a":[1,-2.5e1,true,null],"b":{"c":"x\"y\u0041\n"}} "#),
+            Some(Json::Object(vec![
+                ("a".to_string(),Json::List(vec![Json::Number(1.0),Json::Number(-25.0),Json::Bool(true),Json::Null])),
+                ("b".to_string(),Json::Object(vec![("c".to_string(),Json::Text("x\"yA\n".to_string()))]))
+            ]))
+        );
+        assert_eq!(Json::parse("{}"),Some(Json::Object(vec![])));
+        let nasty = "say \"hi\"\\n\\\u{1}\ttab\r\n";
+        assert_eq!(Json::parse(&json_string(nasty)),Some(Json::Text(nasty.to_string())));
+        assert_eq!(Json::parse("[]"),Some(Json::List(vec![])));
+        for broken in ["","{","{\"a\"}","[1,]","\"open","{\"a\":1} x","nope","{1:2}"] {
+            assert_eq!(Json::parse(broken),None,"{}",broken);
+        }
+        //deep enough is fine, deeper is refused instead of running out of stack
+        let nested = |depth:usize| "[".repeat(depth) + &"]".repeat(depth);
+        assert!(Json::parse(&nested(JSON_DEPTH)).is_some());
+        assert!(Json::parse(&nested(JSON_DEPTH + 1)).is_none());
+        assert!(Json::parse(&nested(1_000_000)).is_none());
+    }
+
+    #[test]
+    fn replies() {
+        assert!(matches!(parse_reply("up"),Some((None,Direction::Up))));
+        assert!(matches!(parse_reply(" LEFT\r"),Some((None,Direction::Left))));
+        assert!(matches!(parse_reply(r#"{"direction":"down"}"#),Some((None,Direction::Down))));
+        assert!(matches!(parse_reply(r#"{"tick":7,"direction":"Right"}"#),Some((Some(7),Direction::Right))));
+        //words that just look like directions aren't answers
+        assert!(parse_reply(r#"{"comment":"up","direction":"sideways"}"#).is_none());
+        assert!(parse_reply("go up").is_none());
+        assert!(parse_reply(r#"{"direction":1}"#).is_none());
+    }
+}
@@ -1,6 +1,5 @@
 pub mod error_handling {
     use std::io::{stderr};
-    use std::fs::File;
     use crossterm::{
         execute, Result,
         style::Print,
@@ -17,10 +16,8 @@ pub mod error_handling {
             stderr(),
             Print(about.red()),
         ).handle();
-        loop { //wait for input before panic!!
-            if let Ok(res) = read() {
-                if let Event::Key(_) = res {break}
-            } else {break}
+        while let Ok(res) = read() { //wait for input before panic!!
+            if let Event::Key(_) = res {break}
         };
         free_window(0,0);
     }
@@ -41,27 +38,11 @@ pub mod error_handling {
         }
     }
 
-    pub trait OpenHandling {
-        fn handle_open(self,filename:&str) -> File;
-    }
-
-    impl OpenHandling for std::io::Result<File> {
-        fn handle_open(self,filename:&str) -> File {
-            match self {
-                Ok(f) => f,
-                Err(_) => {
-                    cry(&format!("Can't open file: \"{}\"",filename));
-                    panic!();
-                }
-            }
-        }
-    }
-
     pub trait WriteHandling {
         fn handle_write(self);
     }
 
-    impl WriteHandling for std::io::Result<usize> {
+    impl WriteHandling for std::io::Result<()> {
         fn handle_write(self) {
             match self {
                 Ok(_) => (),
@@ -93,16 +74,13 @@ pub mod graphics {
     };
     use crate::general::error_handling::TerminalHandling;
 
-    pub fn draw_simple_ascii_picture(ascii:&String,x:u16,y:u16) {
-        let mut pos = 0u16;
-        let mut ascii = ascii.lines();
-        while let Some(line) = ascii.next() {
+    pub fn draw_simple_ascii_picture(ascii:&str,x:u16,y:u16) {
+        for (pos,line) in (0u16..).zip(ascii.lines()) {
             queue!(
                 stdout(),
                 MoveTo(x,y+pos),
                 Print(line)
             ).handle();
-            pos += 1;
         }
     }
 }
@@ -115,7 +93,8 @@ pub mod input {
             Event,
             MouseEventKind,
             MouseButton,
-            KeyCode
+            KeyCode,
+            KeyModifiers
         }
     };
     
@@ -137,6 +116,13 @@ pub mod input {
         Continue,
         Click,
         Draw,
+        Release, //mouse button is up, drag is over
+        Undo,
+        Redo,
+        Save,
+        Key(char), //letters, digits and space, editor tools use them
+        Left, //side arrows of a list, they change the value of the row instead of moving the cursor
+        Right,
         Abort
     }
 
@@ -144,7 +130,7 @@ pub mod input {
         if let Duration::MAX = polltime {} //don't wait for input forever, just read()
         else {
             match poll(polltime) {
-                Ok(result) => if let false = result {
+                Ok(result) => if !result {
                     return Err(InputResult::Continue)
                 },
                 Err(_) => return Err(InputResult::Abort)
@@ -152,28 +138,46 @@ pub mod input {
         }
         
         match read() {
-            Ok(data) => return Ok(data),
-            Err(_) => return Err(InputResult::Abort)
+            Ok(data) => Ok(data),
+            Err(_) => Err(InputResult::Abort)
         }
     }
     
     pub fn cursor_input(cursor:&mut Cursor,polltime:Duration) -> InputResult {
-        if polltime.is_zero() == true { return InputResult::Continue }; //you can't make input faster than 0.000s
-        let input = match receive_input(polltime) {
-            Ok(data) => data,
-            Err(reason) => return reason
-        };
-        match &input {
+        if polltime.is_zero() { return InputResult::Continue }; //you can't make input faster than 0.000s
+        match receive_input(polltime) {
+            Ok(input) => cursor_event(cursor,&input),
+            Err(reason) => reason
+        }
+    }
+
+    //cursor_input for lists, there's nothing to the sides, so left and right arrows are told apart
+    pub fn list_input(cursor:&mut Cursor) -> InputResult {
+        match receive_input(Duration::MAX) {
+            Ok(Event::Key(event)) if event.code == KeyCode::Left => InputResult::Left,
+            Ok(Event::Key(event)) if event.code == KeyCode::Right => InputResult::Right,
+            Ok(input) => cursor_event(cursor,&input),
+            Err(reason) => reason
+        }
+    }
+
+    fn cursor_event(cursor:&mut Cursor,input:&Event) -> InputResult {
+        match input {
             Event::Mouse(event) => {
                 match event.kind {
                     MouseEventKind::Moved => {
                         cursor.x = event.column;
                         cursor.y = event.row;
                     },
-                    MouseEventKind::Down(button) => {
-                        if let MouseButton::Left = button {
-                            return InputResult::Click
-                        }
+                    MouseEventKind::Down(MouseButton::Left) => {
+                        cursor.x = event.column;
+                        cursor.y = event.row;
+                        return InputResult::Click
+                    },
+                    MouseEventKind::Up(MouseButton::Left) => {
+                        cursor.x = event.column;
+                        cursor.y = event.row;
+                        return InputResult::Release
                     },
                     MouseEventKind::Drag(button) => {
                         cursor.x = event.column;
@@ -193,6 +197,10 @@ pub mod input {
                     KeyCode::Down => cursor.y += 1,
                     KeyCode::Enter => return InputResult::Click,
                     KeyCode::Esc => return InputResult::Abort,
+                    KeyCode::Char('z' | 'Z') if event.modifiers.contains(KeyModifiers::CONTROL) => return InputResult::Undo,
+                    KeyCode::Char('y' | 'Y') if event.modifiers.contains(KeyModifiers::CONTROL) => return InputResult::Redo,
+                    KeyCode::Char('s' | 'S') if event.modifiers.contains(KeyModifiers::CONTROL) => return InputResult::Save,
+                    KeyCode::Char(key) => return InputResult::Key(key),
                     _ => ()
                 }
             },
@@ -201,26 +209,121 @@ pub mod input {
         InputResult::Continue
     } //shrinked it so whole function perfectly fits my monitor pog (edit: no more :/)
 
-    pub fn game_input(snake:&mut Snake,speed:Duration) -> InputResult {
+    //same as game_input, but keys don't steer the snake, only ESC and ENTER work
+    pub fn idle_input(speed:Duration) -> InputResult {
         let input = match receive_input(speed) {
             Ok(data) => data,
             Err(reason) => return reason
         };
-        match &input {
-            Event::Key(event) => {
-                if let KeyCode::Esc = event.code {return InputResult::Abort}
-                let dir = match event.code {
-                    KeyCode::Right => Direction::Right,
-                    KeyCode::Left => Direction::Left,
-                    KeyCode::Up => Direction::Up,
-                    KeyCode::Down => Direction::Down,
-                    _ => snake.direction.copy()
-                };
-                snake.last_input = dir.copy();
-                if dir.is_opposite_of(&snake.prev_move) == false { snake.direction = dir }
-            },
-            _ => ()
+        if let Event::Key(event) = &input {
+            match event.code {
+                KeyCode::Esc => return InputResult::Abort,
+                KeyCode::Enter => return InputResult::Click,
+                _ => ()
+            }
+        }
+        InputResult::Continue
+    }
+
+    //the demo goes on until somebody presses anything, moving the mouse doesn't count
+    pub fn demo_input(speed:Duration) -> InputResult {
+        match receive_input(speed) {
+            Ok(Event::Key(_)) => InputResult::Abort,
+            Ok(Event::Mouse(event)) if matches!(event.kind,MouseEventKind::Down(_)) => InputResult::Abort,
+            Ok(_) => InputResult::Continue,
+            Err(reason) => reason
+        }
+    }
+
+    //keys of somebody whose snake lives on another computer, arrows and WASD do the same
+    pub fn direction_input(speed:Duration) -> (InputResult,Option<Direction>) {
+        let input = match receive_input(speed) {
+            Ok(data) => data,
+            Err(reason) => return (reason,None)
+        };
+        let Event::Key(event) = &input else {return (InputResult::Continue,None)};
+        let dir = match event.code {
+            KeyCode::Esc => return (InputResult::Abort,None),
+            KeyCode::Right | KeyCode::Char('d' | 'D') => Direction::Right,
+            KeyCode::Left | KeyCode::Char('a' | 'A') => Direction::Left,
+            KeyCode::Up | KeyCode::Char('w' | 'W') => Direction::Up,
+            KeyCode::Down | KeyCode::Char('s' | 'S') => Direction::Down,
+            _ => return (InputResult::Continue,None)
+        };
+        (InputResult::Continue,Some(dir))
+    }
+
+    //arrows steer the first snake, WASD steers the second one (or the first, if it's alone)
+    pub fn game_input(snakes:&mut [Snake],speed:Duration) -> InputResult {
+        let input = match receive_input(speed) {
+            Ok(data) => data,
+            Err(reason) => return reason
+        };
+        if let Event::Key(event) = &input {
+            let wasd = if snakes.len() > 1 {1} else {0};
+            let (player,dir) = match event.code {
+                KeyCode::Esc => return InputResult::Abort,
+                KeyCode::Enter => return InputResult::Click,
+                KeyCode::Right => (0,Direction::Right),
+                KeyCode::Left => (0,Direction::Left),
+                KeyCode::Up => (0,Direction::Up),
+                KeyCode::Down => (0,Direction::Down),
+                KeyCode::Char('d' | 'D') => (wasd,Direction::Right),
+                KeyCode::Char('a' | 'A') => (wasd,Direction::Left),
+                KeyCode::Char('w' | 'W') => (wasd,Direction::Up),
+                KeyCode::Char('s' | 'S') => (wasd,Direction::Down),
+                _ => return InputResult::Continue
+            };
+            let Some(snake) = snakes.get_mut(player) else {return InputResult::Continue};
+            snake.last_input = dir.copy();
+            if !dir.is_opposite_of(&snake.prev_move) { snake.direction = dir }
         }
         InputResult::Continue
     }
-}
\ No newline at end of file
+}
+
+//every list screen (campaign, modes, puzzles, settings) is a title, rows under it and hints under them
+pub mod list {
+    use crate::{
+        GLOBAL_OFFSET_X,
+        GLOBAL_OFFSET_Y,
+        NONE,
+        general::{graphics::draw_simple_ascii_picture,input::*}
+    };
+
+    pub const LIST_X:u16 = GLOBAL_OFFSET_X;
+    pub const LIST_Y:u16 = GLOBAL_OFFSET_Y + 2; //title, empty line, then rows
+
+    pub struct List {
+        pub selected:usize,
+        rows:usize,
+        cursor:Cursor
+    }
+
+    impl List {
+        pub fn new(rows:usize,selected:usize) -> List {
+            List {selected,rows,cursor:Cursor {x:LIST_X,y:LIST_Y + selected as u16,hover:NONE}}
+        }
+
+        //under the rows, or under whatever is next to them if it's taller
+        pub fn draw_hints(&self,hints:&str,height:usize) {
+            draw_simple_ascii_picture(hints,LIST_X,LIST_Y + self.rows.max(height) as u16 + 1);
+        }
+
+        //row under the cursor is chosen, cursor itself never leaves the rows
+        pub fn input(&mut self) -> InputResult {
+            loop {
+                let input = list_input(&mut self.cursor);
+                if let InputResult::Release = input { //of the click that opened the list
+                    self.cursor.y = LIST_Y + self.selected as u16;
+                    continue
+                }
+                let row = self.cursor.y as i32 - LIST_Y as i32;
+                self.selected = row.clamp(0,self.rows as i32 - 1) as usize;
+                self.cursor.x = LIST_X;
+                self.cursor.y = LIST_Y + self.selected as u16;
+                return input
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use std::{fs,path::Path};
+use rand::{Rng,SeedableRng,thread_rng,rngs::StdRng};
+
+use crate::{
+    Pos,
+    GAME_FIELD_SIZE,
+    MAX_PLAYERS,
+    MAPS_DIR,
+    option,
+    flag,
+    map::{Map,MapFunctionality,Walls,Spawn,map_path,save_map,wall_ring}
+};
+
+pub const STYLES:[&str;4] = ["cave","rooms","maze","arena"];
+pub const DENSITY:f64 = 0.45; //default for --density
+const CAVE_STEPS:usize = 4; //cellular automaton smoothing passes
+const ROOM_SIZE:(usize,usize) = (3,6); //smallest and biggest side of a room
+const ARENA_PILLARS:f64 = 8.0; //pillars in a quarter of the arena at full density
+
+//map of the style from the seed, density goes from 0 (almost empty) to 1 (almost all walls)
+pub fn generate(style:&str,seed:u64,density:f64,spawn:Spawn) -> Option<Map> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let density = density.clamp(0.0,1.0);
+    let mut map = Map::new();
+    map.spawn = spawn;
+    map.walls = match style {
+        "cave" => cave(&mut rng,density),
+        "rooms" => rooms(&mut rng,density),
+        "maze" => maze(&mut rng,density),
+        "arena" => arena(&mut rng,density),
+        _ => return None
+    };
+    wall_ring(&mut map.walls,0);
+    make_playable(&mut map);
+    Some(map)
+}
+
+fn filled() -> Walls {
+    [[true;GAME_FIELD_SIZE];GAME_FIELD_SIZE]
+}
+
+fn inside(x:usize,y:usize) -> bool {
+    x > 0 && y > 0 && x < GAME_FIELD_SIZE - 1 && y < GAME_FIELD_SIZE - 1
+}
+
+//random noise smoothed into blobs: cell becomes a wall when most of its neighbours are walls
+fn cave(rng:&mut StdRng,density:f64) -> Walls {
+    let mut walls = [[false;GAME_FIELD_SIZE];GAME_FIELD_SIZE];
+    for line in walls.iter_mut() {
+        for wall in line.iter_mut() {
+            *wall = rng.gen_bool(density);
+        }
+    }
+    for _ in 0..CAVE_STEPS {
+        let old = walls;
+        for (y,line) in walls.iter_mut().enumerate() {
+            for (x,wall) in line.iter_mut().enumerate() {
+                let around = (-1i32..=1).flat_map(|dy| (-1i32..=1).map(move |dx| (dx,dy)))
+                    .filter(|&(dx,dy)| (dx,dy) != (0,0))
+                    .filter(|&(dx,dy)| {
+                        let (x,y) = (x as i32 + dx,y as i32 + dy);
+                        !inside(x.max(0) as usize,y.max(0) as usize) || old[y as usize][x as usize]
+                    })
+                    .count();
+                if around >= 5 {*wall = true} else if around <= 3 {*wall = false}
+            }
+        }
+    }
+    walls
+}
+
+fn carve_rect(walls:&mut Walls,from:Pos,to:Pos) {
+    for line in walls.iter_mut().take(from.y.max(to.y) as usize + 1).skip(from.y.min(to.y) as usize) {
+        for wall in line.iter_mut().take(from.x.max(to.x) as usize + 1).skip(from.x.min(to.x) as usize) {
+            *wall = false;
+        }
+    }
+}
+
+//L shaped tunnel, first along x then along y
+fn carve_corridor(walls:&mut Walls,from:Pos,to:Pos) {
+    carve_rect(walls,from,Pos {x:to.x,y:from.y});
+    carve_rect(walls,Pos {x:to.x,y:from.y},to);
+}
+
+//rooms dug out of solid rock, every one is connected to the previous one
+fn rooms(rng:&mut StdRng,density:f64) -> Walls {
+    let mut walls = filled();
+    let count = 2 + ((1.0 - density) * 8.0).round() as usize;
+    let mut previous:Option<Pos> = None;
+    for _ in 0..count {
+        let (width,height) = (rng.gen_range(ROOM_SIZE.0..=ROOM_SIZE.1),rng.gen_range(ROOM_SIZE.0..=ROOM_SIZE.1));
+        let x = rng.gen_range(1..GAME_FIELD_SIZE - 1 - width);
+        let y = rng.gen_range(1..GAME_FIELD_SIZE - 1 - height);
+        carve_rect(&mut walls,Pos {x:x as u16,y:y as u16},Pos {x:(x + width - 1) as u16,y:(y + height - 1) as u16});
+        let center = Pos {x:(x + width/2) as u16,y:(y + height/2) as u16};
+        if let Some(previous) = previous {
+            carve_corridor(&mut walls,previous,center);
+        }
+        previous = Some(center);
+    }
+    walls
+}
+
+//recursive backtracker on odd cells, then some walls are knocked out so there's more than one way around
+fn maze(rng:&mut StdRng,density:f64) -> Walls {
+    let mut walls = filled();
+    let start = Pos {x:1,y:1};
+    walls[1][1] = false;
+    let mut stack = vec![start];
+    while let Some(&pos) = stack.last() {
+        let (x,y) = (pos.x as i32,pos.y as i32);
+        let next = [(2,0),(-2,0),(0,2),(0,-2)].into_iter()
+            .map(|(dx,dy)| (x + dx,y + dy))
+            .filter(|&(x,y)| x > 0 && y > 0 && inside(x as usize,y as usize) && walls[y as usize][x as usize])
+            .collect::<Vec<(i32,i32)>>();
+        if next.is_empty() {
+            stack.pop();
+            continue
+        }
+        let (nx,ny) = next[rng.gen_range(0..next.len())];
+        walls[((y + ny)/2) as usize][((x + nx)/2) as usize] = false;
+        walls[ny as usize][nx as usize] = false;
+        stack.push(Pos {x:nx as u16,y:ny as u16});
+    }
+    let knock_out = (1.0 - density) / 2.0;
+    for (y,line) in walls.iter_mut().enumerate() {
+        for (x,wall) in line.iter_mut().enumerate() {
+            if *wall && inside(x,y) && rng.gen_bool(knock_out) {*wall = false}
+        }
+    }
+    walls
+}
+
+//pillars in one quarter, mirrored to the other three so nobody's spawn is better
+fn arena(rng:&mut StdRng,density:f64) -> Walls {
+    let mut walls = [[false;GAME_FIELD_SIZE];GAME_FIELD_SIZE];
+    let half = GAME_FIELD_SIZE / 2;
+    let last = GAME_FIELD_SIZE - 1;
+    for _ in 0..(density * ARENA_PILLARS).round() as usize {
+        let (width,height) = (rng.gen_range(1..=3),rng.gen_range(1..=3));
+        let x = rng.gen_range(2..half - width + 1);
+        let y = rng.gen_range(2..half - height + 1);
+        for y in y..y + height {
+            for x in x..x + width {
+                for (x,y) in [(x,y),(last - x,y),(x,last - y),(last - x,last - y)] {
+                    walls[y][x] = true;
+                }
+            }
+        }
+    }
+    walls
+}
+
+//spawns are cleared, all of them are connected to the first one and cells nobody can reach are walled up
+fn make_playable(map:&mut Map) {
+    for cell in map.spawn.area() {
+        map.walls[cell.y as usize][cell.x as usize] = false;
+    }
+    let first = map.spawn.pos;
+    for player in 1..MAX_PLAYERS {
+        carve_corridor(&mut map.walls,map.spawn.player(player).pos,first);
+    }
+    let mut reached = [[false;GAME_FIELD_SIZE];GAME_FIELD_SIZE];
+    reached[first.y as usize][first.x as usize] = true;
+    let mut stack = vec![first];
+    while let Some(pos) = stack.pop() {
+        let (x,y) = (pos.x as usize,pos.y as usize);
+        for (x,y) in [(x.wrapping_sub(1),y),(x + 1,y),(x,y.wrapping_sub(1)),(x,y + 1)] {
+            if x >= GAME_FIELD_SIZE || y >= GAME_FIELD_SIZE || reached[y][x] || map.walls[y][x] {continue}
+            reached[y][x] = true;
+            stack.push(Pos {x:x as u16,y:y as u16});
+        }
+    }
+    for (walls,reached) in map.walls.iter_mut().zip(reached) {
+        for (wall,reached) in walls.iter_mut().zip(reached) {
+            *wall = !reached;
+        }
+    }
+}
+
+//`snake generate` - writes a generated map to --out, or to the maps folder named after style and seed,
+//a map that is there already stays unless --force says otherwise
+pub fn run() {
+    let style = option("--style").unwrap_or(STYLES[0].to_string());
+    let seed = option("--seed").and_then(|n| n.parse().ok()).unwrap_or_else(|| thread_rng().gen());
+    let density = option("--density").and_then(|n| n.parse().ok()).unwrap_or(DENSITY);
+    let Some(map) = generate(&style,seed,density,Spawn::new()) else {
+        eprintln!("Unknown style \"{}\", available styles: {}",style,STYLES.join(", "));
+        return
+    };
+    let (file,name) = match option("--out") {
+        Some(file) => (file,None),
+        None => {
+            fs::create_dir_all(MAPS_DIR).ok();
+            let name = format!("{}_{}",style,seed);
+            (map_path(&name),Some(name))
+        }
+    };
+    if Path::new(&file).exists() && !flag("--force") {
+        eprintln!("\"{}\" already exists, --force overwrites it",file);
+        std::process::exit(1);
+    }
+    match name {
+        Some(name) => {save_map(&map,&name);}, //old unsaved changes of that map don't belong to it anymore
+        None => map.save(&file)
+    }
+    println!("{} map, seed {}, density {} written to \"{}\"",style,seed,density,file);
+}
@@ -0,0 +1,108 @@
+use std::{
+    io::stdout,
+    fs
+};
+
+use crossterm::{
+    queue,
+    cursor::MoveTo,
+    style::Print
+};
+
+use crate::{
+    Pos,
+    GHOSTS_DIR,
+    GAME_FIELD_OFFSET_X,
+    GAME_FIELD_OFFSET_Y,
+    SnakeFunctionality,
+    snake_signs,
+    erase_cells,
+    general::error_handling::*,
+    engine::Game,
+    obstacles::ObstacleFunctionality,
+    map::write_atomically,
+    net::{State,state_line,parse_state,over_line},
+    replay::round_lines,
+    theme::Theme
+};
+
+//best runs are kept as replay files, so they can be watched with --watch-file too
+fn path(name:&str) -> String {
+    format!("{}/{}.txt",GHOSTS_DIR,name)
+}
+
+//the game being played, written down only if it turns out better than the ghost
+pub struct Run {
+    lines:Vec<String>
+}
+
+impl Run {
+    pub fn start(game:&Game,player:&str) -> Run {
+        Run {lines:round_lines(game,&[player.to_string()]).to_vec()}
+    }
+
+    pub fn step(&mut self,game:&Game) {
+        self.lines.push(state_line(game));
+    }
+
+    pub fn save(mut self,name:&str) {
+        self.lines.push(over_line(None,&[]));
+        let text = self.lines.join("\n") + "\n";
+        //no ghost next time is all that happens if the folder or the file can't be written
+        if fs::create_dir_all(GHOSTS_DIR).is_ok() {
+            write_atomically(&path(name),&text).ok();
+        }
+    }
+}
+
+//personal best on the same map, drawn under the live snake and never hit
+pub struct Ghost {
+    states:Vec<State>, //one per tick, starting with the tick 0
+    shown:Vec<Pos>
+}
+
+impl Ghost {
+    pub fn load(name:&str) -> Option<Ghost> {
+        let text = fs::read_to_string(path(name)).ok()?;
+        let states = text.lines().filter_map(parse_state).filter(|state| !state.snakes.is_empty()).collect::<Vec<State>>();
+        if states.is_empty() {return None}
+        Some(Ghost {states,shown:vec![]})
+    }
+
+    //after its last tick the ghost stays where it ended
+    fn at(&self,tick:u32) -> &State {
+        self.states.get(tick as usize).unwrap_or(self.states.last().unwrap())
+    }
+
+    pub fn score(&self) -> u16 {
+        self.states.last().unwrap().snakes[0].score
+    }
+
+    pub fn ticks(&self) -> u32 {
+        self.states.last().unwrap().tick
+    }
+
+    pub fn score_at(&self,tick:u32) -> u16 {
+        self.at(tick).snakes[0].score
+    }
+
+    //the whole ghost every tick, so cells the live snake just left get it back
+    pub fn draw(&mut self,game:&Game,theme:&Theme) {
+        let signs = snake_signs(&self.at(game.tick).snakes[0],theme);
+        let gone = self.shown.iter().filter(|cell| !signs.iter().any(|(pos,_)| pos == *cell)).copied().collect::<Vec<Pos>>();
+        erase_cells(&gone,game,theme);
+        for (cell,sign) in &signs {
+            let taken = game.walls[cell.y as usize][cell.x as usize] ||
+                game.snakes.iter().any(|snake| snake.is_in_point(cell.x,cell.y)) ||
+                *cell == game.fruit.pos || game.leftovers.contains(cell) ||
+                game.obstacles.iter().any(|o| o.is_in_point(cell.x,cell.y));
+            if taken {continue}
+            queue!(
+                stdout(),
+                MoveTo(cell.x + GAME_FIELD_OFFSET_X,cell.y + GAME_FIELD_OFFSET_Y),
+                Print(theme.ghost_sign(*sign))
+            ).handle();
+        }
+        self.shown = signs.into_iter().map(|(cell,_)| cell).collect();
+    }
+}
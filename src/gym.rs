@@ -0,0 +1,321 @@
+use std::io::{stdin,stdout,BufRead,Write};
+
+use crate::{
+    Pos,
+    Direction,
+    GAME_FIELD_SIZE,
+    option,
+    engine::{Game,GameFunctionality,DeathCause},
+    map::{Map,MapFunctionality},
+    obstacles::ObstacleFunctionality,
+    external::{direction_name,parse_direction,json_string}
+};
+
+pub const IDLE_LIMIT:u32 = 500; //ticks without eating before episode is cut, default for --max-idle
+const WINDOW_SIZE:usize = 11; //default for --obs window
+
+//how much each thing is worth for the learning agent
+pub struct Rewards {
+    pub fruit:f32,
+    pub death:f32,
+    pub step:f32, //every tick, negative values make agent hurry
+    pub closer:f32 //for getting closer to the fruit, the same is taken back for moving away
+}
+
+pub enum Encoding {
+    Planes, //whole field, one layer for walls, obstacles, body, head and fruit
+    Window(usize) //square around the head, turned so the snake always looks up
+}
+
+pub struct Observation {
+    pub shape:Vec<usize>,
+    pub data:Vec<f32>
+}
+
+pub struct Info {
+    pub score:u16,
+    pub tick:u32,
+    pub death:Option<DeathCause>,
+    pub timeout:bool
+}
+
+pub struct Env {
+    map:Map,
+    game:Game,
+    rewards:Rewards,
+    encoding:Encoding,
+    max_idle:u32,
+    idle:u32,
+    done:bool //snake died or took too long, only reset can go on
+}
+
+fn wrap(value:i32) -> usize {
+    value.rem_euclid(GAME_FIELD_SIZE as i32) as usize
+}
+
+fn distance(a:&Pos,b:&Pos) -> i32 {
+    let size = GAME_FIELD_SIZE as i32;
+    let dx = (a.x as i32 - b.x as i32).abs();
+    let dy = (a.y as i32 - b.y as i32).abs();
+    dx.min(size - dx) + dy.min(size - dy)
+}
+
+pub fn death_name(death:&Option<DeathCause>,timeout:bool) -> &'static str {
+    match death {
+        Some(DeathCause::Wall) => "wall",
+        Some(DeathCause::Snake) => "self",
+        Some(DeathCause::Rival) => "rival",
+        Some(DeathCause::HeadOn) => "head-on",
+        Some(DeathCause::Obstacle) => "obstacle",
+        Some(DeathCause::Left) => "left",
+        None => if timeout {"timeout"} else {"none"}
+    }
+}
+
+impl Env {
+    pub fn new(map:Map,rewards:Rewards,encoding:Encoding,max_idle:u32) -> Env {
+        let game = Game::new(&map,0,1);
+        Env {map,game,rewards,encoding,max_idle,idle:0,done:false}
+    }
+
+    pub fn reset(&mut self,seed:u64) -> Observation {
+        self.game = Game::new(&self.map,seed,1);
+        self.idle = 0;
+        self.done = false;
+        self.observe()
+    }
+
+    pub fn step(&mut self,action:Direction) -> Result<(Observation,f32,bool,Info),&'static str> {
+        if self.done {
+            return Err("episode is over, reset it first")
+        }
+        let before = distance(&self.game.snakes[0].pos,&self.game.fruit.pos);
+        let step = self.game.step(&[Some(action)]);
+        let death = step.deaths[0];
+        let mut reward = self.rewards.step;
+        if step.ate[0] {
+            reward += self.rewards.fruit;
+            self.idle = 0;
+        } else {
+            self.idle += 1;
+            let after = distance(&self.game.snakes[0].pos,&self.game.fruit.pos);
+            reward += self.rewards.closer * (before - after).signum() as f32;
+        }
+        if death.is_some() {
+            reward += self.rewards.death;
+        }
+        let timeout = death.is_none() && self.idle >= self.max_idle;
+        let info = Info {
+            score:self.game.snakes[0].score,
+            tick:self.game.tick,
+            death,
+            timeout
+        };
+        self.done = !self.game.snakes[0].alive || timeout;
+        let reward = reward.clamp(f32::MIN,f32::MAX); //huge rewards add up to inf, which isn't json
+        Ok((self.observe(),reward,self.done,info))
+    }
+
+    pub fn observe(&self) -> Observation {
+        match self.encoding {
+            Encoding::Planes => self.planes(),
+            Encoding::Window(size) => self.window(size)
+        }
+    }
+
+    fn planes(&self) -> Observation {
+        let area = GAME_FIELD_SIZE*GAME_FIELD_SIZE;
+        let mut data = vec![0.0;5*area];
+        let mut set = |plane:usize,pos:&Pos| data[plane*area + pos.y as usize*GAME_FIELD_SIZE + pos.x as usize] = 1.0;
+        for (y,line) in (0u16..).zip(&self.game.walls) {
+            for (x,wall) in (0u16..).zip(line) {
+                if *wall {set(0,&Pos{x,y})}
+            }
+        }
+        for cell in self.game.obstacles.iter().flat_map(|o| o.cells()) {
+            set(1,&cell);
+        }
+        for part in &self.game.snakes[0].body {
+            set(2,part);
+        }
+        set(3,&self.game.snakes[0].pos);
+        set(4,&self.game.fruit.pos);
+        Observation {shape:vec![5,GAME_FIELD_SIZE,GAME_FIELD_SIZE],data}
+    }
+
+    fn window(&self,size:usize) -> Observation {
+        let area = size*size;
+        let mut data = vec![0.0;4*area];
+        let head = &self.game.snakes[0].pos;
+        let obstacles = self.game.obstacles.iter().flat_map(|o| o.cells()).collect::<Vec<Pos>>();
+        let half = (size/2) as i32;
+        for wy in 0..size {
+            for wx in 0..size {
+                let (rx,ry) = (wx as i32 - half,wy as i32 - half); //as if snake looks up
+                let (dx,dy) = match self.game.snakes[0].direction {
+                    Direction::Up => (rx,ry),
+                    Direction::Right => (-ry,rx),
+                    Direction::Down => (-rx,-ry),
+                    Direction::Left => (ry,-rx)
+                };
+                let pos = Pos {
+                    x:wrap(head.x as i32 + dx) as u16,
+                    y:wrap(head.y as i32 + dy) as u16
+                };
+                let cell = wy*size + wx;
+                if self.game.walls[pos.y as usize][pos.x as usize] {data[cell] = 1.0}
+                if obstacles.contains(&pos) {data[area + cell] = 1.0}
+                if self.game.snakes[0].body.contains(&pos) {data[2*area + cell] = 1.0}
+                if self.game.fruit.pos == pos {data[3*area + cell] = 1.0}
+            }
+        }
+        Observation {shape:vec![4,size,size],data}
+    }
+}
+
+fn observation_json(observation:&Observation) -> String {
+    format!(
+        "\"shape\":[{}],\"observation\":[{}]",
+        observation.shape.iter().map(|n| n.to_string()).collect::<Vec<String>>().join(","),
+        observation.data.iter().map(|v| if *v == 0.0 {"0"} else {"1"}).collect::<Vec<&str>>().join(",")
+    )
+}
+
+fn float_option(name:&str,default:f32) -> f32 {
+    option(name).and_then(|value| value.parse().ok()).filter(|value:&f32| value.is_finite()).unwrap_or(default)
+}
+
+//`snake gym` - environment for reinforcement learning, controlled through stdin/stdout:
+//"reset <seed>" starts new episode, "step <up|right|down|left>" makes a move,
+//every command is answered with one json line
+pub fn run() {
+    let map = match option("--map") {
+        Some(file) => Map::load(&file),
+        None => Map::new()
+    };
+    let rewards = Rewards {
+        fruit:float_option("--reward-fruit",1.0),
+        death:float_option("--reward-death",-1.0),
+        step:float_option("--reward-step",0.0),
+        closer:float_option("--reward-closer",0.0)
+    };
+    let encoding = match option("--obs") {
+        Some(obs) if obs.starts_with("window") => Encoding::Window(
+            obs.trim_start_matches("window").trim_start_matches(':').parse().unwrap_or(WINDOW_SIZE)
+        ),
+        _ => Encoding::Planes
+    };
+    let max_idle = option("--max-idle").and_then(|value| value.parse().ok()).unwrap_or(IDLE_LIMIT);
+    let mut env = Env::new(map,rewards,encoding,max_idle);
+
+    let mut out = stdout();
+    for line in stdin().lock().lines() {
+        let Ok(line) = line else {break};
+        let mut words = line.split_whitespace();
+        let reply = match (words.next(),words.next()) {
+            (Some("reset"),seed) => {
+                let seed = seed.and_then(|s| s.parse().ok()).unwrap_or(0);
+                format!("{{{}}}",observation_json(&env.reset(seed)))
+            },
+            (Some("step"),Some(action)) => match parse_direction(action).map(|action| env.step(action)) {
+                Some(Ok((observation,reward,done,info))) => {
+                    format!(
+                        "{{{},\"reward\":{},\"done\":{},\"info\":{{\"score\":{},\"tick\":{},\"death\":\"{}\",\"direction\":\"{}\"}}}}",
+                        observation_json(&observation),
+                        reward,
+                        done,
+                        info.score,
+                        info.tick,
+                        death_name(&info.death,info.timeout),
+                        direction_name(&env.game.snakes[0].direction)
+                    )
+                },
+                Some(Err(error)) => format!("{{\"error\":{}}}",json_string(error)),
+                None => format!("{{\"error\":{}}}",json_string(&format!("unknown action '{}'",action)))
+            },
+            _ => format!("{{\"error\":{}}}",json_string(&format!("unknown command '{}'",line)))
+        };
+        if writeln!(out,"{}",reply).and_then(|_| out.flush()).is_err() {break}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::Spawn;
+
+    //empty field with the border, snake looks right from the middle with fruit two cells ahead
+    fn env(encoding:Encoding,rewards:Rewards) -> Env {
+        let mut map = Map::new();
+        map.spawn = Spawn {pos:Pos {x:8,y:10},direction:Direction::Right};
+        map.fruits = vec![Pos {x:10,y:10}];
+        Env::new(map,rewards,encoding,IDLE_LIMIT)
+    }
+
+    fn rewards() -> Rewards {
+        Rewards {fruit:1.0,death:-1.0,step:-0.01,closer:0.1}
+    }
+
+    fn at(observation:&Observation,plane:usize,pos:Pos) -> f32 {
+        let size = observation.shape[1];
+        observation.data[plane*size*size + pos.y as usize*size + pos.x as usize]
+    }
+
+    #[test]
+    fn reset_and_step() {
+        let mut env = env(Encoding::Planes,rewards());
+        let observation = env.reset(7);
+        assert!(observation.shape == [5,GAME_FIELD_SIZE,GAME_FIELD_SIZE]);
+        assert_eq!(at(&observation,0,Pos {x:0,y:0}),1.0);
+        assert_eq!(at(&observation,2,Pos {x:7,y:10}),1.0);
+        assert_eq!(at(&observation,3,Pos {x:8,y:10}),1.0);
+        assert_eq!(at(&observation,4,Pos {x:10,y:10}),1.0);
+        assert!(env.reset(7).data == observation.data);
+
+        let (observation,reward,done,info) = env.step(Direction::Right).unwrap();
+        assert_eq!(reward,-0.01 + 0.1);
+        assert!(!done && info.tick == 1 && info.score == 0);
+        assert_eq!(at(&observation,3,Pos {x:9,y:10}),1.0);
+        let (_,reward,done,info) = env.step(Direction::Right).unwrap();
+        assert_eq!(reward,-0.01 + 1.0);
+        assert!(!done && info.score == 1);
+    }
+
+    #[test]
+    fn death_ends_episode() {
+        let mut env = env(Encoding::Planes,rewards());
+        env.map.walls[10][9] = true;
+        env.reset(1);
+        let (_,reward,done,info) = env.step(Direction::Right).unwrap();
+        assert_eq!(reward,-0.01 + 0.1 - 1.0); //it did get closer to the fruit before dying
+        assert!(done && matches!(info.death,Some(DeathCause::Wall)));
+        assert!(env.step(Direction::Right).is_err());
+        assert!(env.reset(1).data.contains(&1.0));
+        assert!(env.step(Direction::Up).is_ok());
+    }
+
+    #[test]
+    fn huge_rewards_stay_finite() {
+        let mut env = env(Encoding::Planes,Rewards {fruit:f32::MAX,death:0.0,step:f32::MAX,closer:0.0});
+        env.reset(1);
+        env.step(Direction::Right).unwrap();
+        let (_,reward,_,_) = env.step(Direction::Right).unwrap();
+        assert_eq!(reward,f32::MAX);
+    }
+
+    #[test]
+    fn window_looks_where_snake_goes() {
+        let mut env = env(Encoding::Window(3),rewards());
+        let observation = env.reset(1);
+        assert!(observation.shape == [4,3,3]);
+        let (observation,_,_,_) = env.step(Direction::Right).unwrap(); //fruit right in front, body behind
+        assert_eq!(at(&observation,3,Pos {x:1,y:0}),1.0);
+        assert_eq!(at(&observation,2,Pos {x:1,y:2}),1.0);
+        assert_eq!(observation.data.iter().filter(|v| **v == 1.0).count(),2);
+
+        let (observation,_,_,_) = env.step(Direction::Down).unwrap(); //looking down, west is on its right
+        assert_eq!(at(&observation,2,Pos {x:1,y:2}),1.0);
+        assert_eq!(at(&observation,2,Pos {x:2,y:2}),1.0);
+        assert_eq!(at(&observation,2,Pos {x:0,y:2}),0.0);
+    }
+}
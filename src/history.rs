@@ -0,0 +1,73 @@
+const HISTORY_LIMIT:usize = 200; //oldest steps are forgotten after that
+
+//snapshots for undo/redo, current state is never kept here, only the ones around it
+pub struct History<T:Clone> {
+    undo:Vec<T>,
+    redo:Vec<T>
+}
+
+impl<T:Clone> History<T> {
+    pub fn new() -> History<T> {
+        History {undo:vec![],redo:vec![]}
+    }
+
+    //call before changing anything
+    pub fn record(&mut self,current:&T) {
+        self.undo.push(current.clone());
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    pub fn undo(&mut self,current:&T) -> Option<T> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current.clone());
+        Some(previous)
+    }
+
+    pub fn redo(&mut self,current:&T) -> Option<T> {
+        let next = self.redo.pop()?;
+        self.undo.push(current.clone());
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undoes_and_redoes() {
+        let mut history = History::new();
+        let mut state = 0;
+        for next in 1..=3 {
+            history.record(&state);
+            state = next;
+        }
+        state = history.undo(&state).unwrap();
+        state = history.undo(&state).unwrap();
+        assert_eq!(state,1);
+        state = history.redo(&state).unwrap();
+        assert_eq!(state,2);
+        //a new change makes the undone ones unreachable
+        history.record(&state);
+        state = 10;
+        assert!(history.redo(&state).is_none());
+        assert_eq!(history.undo(&state),Some(2));
+    }
+
+    #[test]
+    fn forgets_the_oldest() {
+        let mut history = History::new();
+        for state in 0..HISTORY_LIMIT + 5 {
+            history.record(&state);
+        }
+        let mut state = HISTORY_LIMIT + 5;
+        while let Some(previous) = history.undo(&state) {
+            state = previous;
+        }
+        assert_eq!(state,5);
+        assert!(History::<u8>::new().undo(&0).is_none());
+    }
+}
@@ -1,13 +1,50 @@
 use std::{
     io::{stdout,Write},
     fs,
-    fs::OpenOptions,
     time::{SystemTime,Duration},
-    collections::VecDeque
+    collections::VecDeque,
+    iter::successors
 };
 
 mod general;
+mod map;
+mod obstacles;
+mod bots;
+mod external;
+mod engine;
+mod gym;
+mod bench;
+mod net;
+mod replay;
+mod history;
+mod tools;
+mod browser;
+mod generator;
+mod campaign;
+mod modes;
+mod scores;
+mod puzzle;
+mod daily;
+mod ghost;
+mod theme;
+mod settings;
 use crate::general::{error_handling::*,graphics::*,input::*};
+use crate::map::{
+    Map,MapFunctionality,Walls,Spawn,reset_map,map_path,map_exists,list_maps,clean_map_name,starting_map,
+    open_map,save_map,autosave,keep_unsaved,forget_recovery,rename_map,delete_map
+};
+use crate::obstacles::{Obstacle,ObstacleFunctionality};
+use crate::bots::{Controller,Autopilot,BOTS,SKILLS,bot_by_name,rival_by_skill};
+use crate::external::ExternalBot;
+use crate::engine::{Game,GameFunctionality,Step,Corpses,Rules};
+use crate::net::{Server,Connection,State,PORT,ANY_ADDRESS,state_line,walls_line,over_line,clean_name,default_name};
+use crate::replay::{Recorder,tail};
+use crate::history::History;
+use crate::tools::{Palette,Tool};
+use crate::browser::{browse,prompt};
+use crate::generator::{generate,STYLES,DENSITY};
+use crate::theme::Theme;
+use crate::settings::Settings;
 
 use crossterm::{
     execute, queue,
@@ -26,7 +63,7 @@ use crossterm::{
     },
     event::{EnableMouseCapture,DisableMouseCapture,read,Event},
     cursor::{Hide,Show,MoveTo},
-    style::{Print,Stylize,SetForegroundColor,SetBackgroundColor,Color}
+    style::{Print,Stylize,SetForegroundColor,SetBackgroundColor,Color,StyledContent}
 };
 
 use rand::{thread_rng,Rng};
@@ -37,6 +74,7 @@ enum Screen {
     Game
 }
 
+#[derive(Clone,Copy,PartialEq)]
 struct Pos {
     x:u16,
     y:u16
@@ -59,6 +97,7 @@ pub enum Direction {
 
 trait DirectionFunctionality {
     fn copy(&self) -> Direction;
+    fn opposite(&self) -> Direction;
     fn is_opposite_of(&self, direction:&Direction) -> bool;
 }
 impl DirectionFunctionality for Direction {
@@ -70,12 +109,20 @@ impl DirectionFunctionality for Direction {
             Direction::Down => Direction::Down
         }
     }
+    fn opposite(&self) -> Direction {
+        match self {
+            Direction::Right => Direction::Left,
+            Direction::Left => Direction::Right,
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up
+        }
+    }
     fn is_opposite_of(&self, direction: &Direction) -> bool {
         match self {
-            Direction::Right => if let Direction::Left = direction {true} else {false},
-            Direction::Left => if let Direction::Right = direction {true} else {false},
-            Direction::Up => if let Direction::Down = direction {true} else {false},
-            Direction::Down => if let Direction::Up = direction {true} else {false}
+            Direction::Right => matches!(direction,Direction::Left),
+            Direction::Left => matches!(direction,Direction::Right),
+            Direction::Up => matches!(direction,Direction::Down),
+            Direction::Down => matches!(direction,Direction::Up)
         }
     }
 }
@@ -87,44 +134,38 @@ pub struct Snake {
     direction:Direction,
     alive:bool,
     prev_move:Direction,
-    last_input:Direction
+    last_input:Direction,
+    score:u16
 }
 
 trait SnakeFunctionality {
-    fn new() -> Snake;
+    fn new(head:Pos,direction:Direction) -> Self;
+    fn player(spawn:&Spawn,index:usize) -> Self;
     fn is_in_point(&self,x:u16,y:u16) -> bool;
 }
 
 impl SnakeFunctionality for Snake {
-    fn new() -> Snake {
+    fn new(head:Pos,direction:Direction) -> Snake {
+        let neck = next_pos(&head,&direction.opposite());
+        let tail = next_pos(&neck,&direction.opposite());
         Snake {
-            pos:Pos{
-                x:SNAKE_SPAWN_POS_X+2,
-                y:SNAKE_SPAWN_POS_Y
-            },
-            body:VecDeque::from([
-                Pos{
-                    x:SNAKE_SPAWN_POS_X,
-                    y:SNAKE_SPAWN_POS_Y
-                },
-                Pos{
-                    x:SNAKE_SPAWN_POS_X+1,
-                    y:SNAKE_SPAWN_POS_Y
-                }
-            ]),
-            direction:Direction::Right,
+            pos:head,
+            body:VecDeque::from([tail,neck]),
+            direction:direction.copy(),
             alive:true,
-            prev_move:Direction::Right,
-            last_input:Direction::Right
+            prev_move:direction.copy(),
+            last_input:direction,
+            score:0
         }
     }
+    //first player starts where the map says, the others get the same spawn turned to their corners
+    fn player(spawn:&Spawn,index:usize) -> Snake {
+        let spawn = spawn.player(index);
+        Snake::new(spawn.pos,spawn.direction)
+    }
     fn is_in_point(&self,x:u16,y:u16) -> bool {
         if (self.pos.y == y) && (self.pos.x == x) {return true}
-        let mut iter = self.body.iter();
-        while let Some(body) = iter.next() {
-            if (body.y == y) && (body.x == x) {return true}
-        }
-        false
+        self.body.iter().any(|body| (body.y == y) && (body.x == x))
     }
 }
 
@@ -133,52 +174,44 @@ struct Fruit {
 }
 
 trait FruitFunctionality {
-    fn new(map:&[[bool;GAME_FIELD_SIZE];GAME_FIELD_SIZE]) -> Fruit;
-    fn respawn(&mut self,map:&[[bool;GAME_FIELD_SIZE];GAME_FIELD_SIZE],snake:&Snake);
+    fn new<R:Rng>(map:&Walls,snakes:&[Snake],obstacles:&[Obstacle],rng:&mut R) -> Self;
+    fn respawn<R:Rng>(&mut self,map:&Walls,snakes:&[Snake],obstacles:&[Obstacle],rng:&mut R);
 }
 
 impl FruitFunctionality for Fruit {
-    fn new(map:&[[bool;GAME_FIELD_SIZE];GAME_FIELD_SIZE]) -> Fruit {
+    fn new<R:Rng>(map:&Walls,snakes:&[Snake],obstacles:&[Obstacle],rng:&mut R) -> Fruit {
         let mut f = Fruit {
             pos: Pos{x:0,y:0} 
         };
-        f.respawn(map,&mut Snake::new());
+        f.respawn(map,snakes,obstacles,rng);
         f
     }
-    fn respawn(&mut self,map:&[[bool;GAME_FIELD_SIZE];GAME_FIELD_SIZE],snake:&Snake) {
+    fn respawn<R:Rng>(&mut self,map:&Walls,snakes:&[Snake],obstacles:&[Obstacle],rng:&mut R) {
+        let taken = |x:u16,y:u16| snakes.iter().any(|snake| snake.is_in_point(x,y)) ||
+            obstacles.iter().any(|obstacle| obstacle.is_in_point(x,y));
         //damn i'm always getting suprised how large simple code can become in rust
-        let mut rng = thread_rng();
         let mut lines = vec![];
-        let mut num = 0u16;
-        for line in map {
-            let mut x = 0u16;
-            for val in line { // filtering out all lines with no free space
-                if val == &false &&
-                    snake.is_in_point(x,num) == false {
-                        lines.push(num);
-                        break;
-                }
-                x += 1;
+        for (num,line) in (0u16..).zip(map) {
+            // filtering out all lines with no free space
+            if (0u16..).zip(line).any(|(x,val)| !*val && !taken(x,num)) {
+                lines.push(num);
             }
-            num += 1;
         } 
-        let size = lines.iter().count();
+        let size = lines.len();
         if size == 0 {
             self.pos = Pos {x: 0, y: 0};
             return
         }
         let line = rng.gen_range(0..size); // selecting line randomly
         let mut points = vec![];
-        let mut num = 0u16;
-        for row in map[lines[line] as usize] {
-            if row == false && 
-                snake.is_in_point(num, lines[line]) == false { // filtering out all walls from selected line
+        for (num,row) in (0u16..).zip(map[lines[line] as usize]) {
+            if !row && 
+                !taken(num, lines[line]) { // filtering out all walls from selected line
                     points.push(num)
             }
-            num += 1;
         } 
 
-        let size = points.iter().count();
+        let size = points.len();
         let point = rng.gen_range(0..size); // selecting point in line randomly
         self.pos = Pos {
             x: points[point],
@@ -193,27 +226,127 @@ const FILE_BUTTONS:&str = "buttons.txt";
 const FILE_TITLECOLORS:&str = "titlecolors.txt";
 const FILE_EDITOR:&str = "editor.txt";
 const FILE_GAME:&str = "game.txt";
-const FILE_MAP:&str = "map.txt";
+const FILE_MAP:&str = "map.txt"; //where the only map used to be, now it's moved to MAPS_DIR
+const MAPS_DIR:&str = "maps";
+const DEFAULT_MAP:&str = "default";
+const CAMPAIGN_DIR:&str = "campaign"; //levels list and their maps
+const PUZZLES_DIR:&str = "puzzles"; //maps with fruit sequences
+const FILE_PROGRESS:&str = "progress.txt"; //stars got in the campaign
+const FILE_SCORES:&str = "scores.txt"; //high scores of every game mode
+const FILE_DAILY_EXPORT:&str = "daily.csv"; //daily challenge scores to compare with others
+const GHOSTS_DIR:&str = "ghosts"; //replays of the best runs to race against
+const THEMES_DIR:&str = "themes"; //glyphs and colors to draw the game with
+const FILE_SETTINGS:&str = "settings.txt"; //what was chosen in the settings
 const MENU_TICK:u64 = 250; //lower = faster
 const GAME_TICK :u64 = 250; //lower = faster
+const BOT_TIMEOUT:u64 = 100; //ms external bot has to answer, default for --bot-timeout
+const DEMO_RESTART_TICKS:u16 = 8; //how long bot stares at its own death before trying again
+const DEMO_IDLE:u64 = 20000; //ms nothing has to happen in the main menu before autopilot shows the game
+const SHRINK_TICKS:u32 = 40; //how often border moves in battle royale, default for --shrink
 const NONE:u8 = 0; //"no button selected" constant
 const BUTTON_PLAY:u8 = 1;
 const BUTTON_EXIT:u8 = 2;
 const BUTTON_EDIT:u8 = 3;
+const BUTTON_VERSUS:u8 = 4;
+const BUTTON_MAPS:u8 = 5;
+const BUTTON_CAMPAIGN:u8 = 6;
+const BUTTON_MODES:u8 = 7;
+const BUTTON_PUZZLES:u8 = 8;
+const BUTTON_DAILY:u8 = 9;
+const BUTTON_SETTINGS:u8 = 10;
 const GLOBAL_OFFSET_X:u16 = 1;
 const GLOBAL_OFFSET_Y:u16 = 1;
 const BUTTONS_POS :Pos = Pos {
     x:GLOBAL_OFFSET_X,
     y:13
 };
-const EDIT_RESET_BUTTON:Button = Button {
-    x: EDIT_HINT_OFFSET_X,
-    y: EDIT_HINT_OFFSET_Y + 8,
-    width: 6,
-    height: 2,
-    return_code: 1
-};
-const MAINMENU_BUTTONS:[Button;3] = [
+const EDIT_RESET:u8 = 1;
+const EDIT_UNDO:u8 = 2;
+const EDIT_REDO:u8 = 3;
+const EDIT_PALETTE:u8 = 4; //any of the tools under the hint
+const EDIT_NEW:u8 = 5;
+const EDIT_SAVE_AS:u8 = 6;
+const EDIT_RENAME:u8 = 7;
+const EDIT_COPY:u8 = 8;
+const EDIT_DELETE:u8 = 9;
+const EDIT_SAVE:u8 = 10;
+const EDIT_GENERATE:u8 = 11;
+const EDIT_MAP_LABELS:[&str;7] = ["SAVE","NEW","SAVE AS","RENAME","COPY","DELETE","GENERATE"];
+const EDIT_MAP_BUTTONS:[Button;7] = [
+    Button {
+        x: EDIT_HINT_OFFSET_X,
+        y: EDIT_MAP_OFFSET_Y + 1,
+        width: 3,
+        height: 0,
+        return_code: EDIT_SAVE
+    },
+    Button {
+        x: EDIT_HINT_OFFSET_X + 6,
+        y: EDIT_MAP_OFFSET_Y + 1,
+        width: 2,
+        height: 0,
+        return_code: EDIT_NEW
+    },
+    Button {
+        x: EDIT_HINT_OFFSET_X + 11,
+        y: EDIT_MAP_OFFSET_Y + 1,
+        width: 6,
+        height: 0,
+        return_code: EDIT_SAVE_AS
+    },
+    Button {
+        x: EDIT_HINT_OFFSET_X + 20,
+        y: EDIT_MAP_OFFSET_Y + 1,
+        width: 5,
+        height: 0,
+        return_code: EDIT_RENAME
+    },
+    Button {
+        x: EDIT_HINT_OFFSET_X + 28,
+        y: EDIT_MAP_OFFSET_Y + 1,
+        width: 3,
+        height: 0,
+        return_code: EDIT_COPY
+    },
+    Button {
+        x: EDIT_HINT_OFFSET_X + 34,
+        y: EDIT_MAP_OFFSET_Y + 1,
+        width: 5,
+        height: 0,
+        return_code: EDIT_DELETE
+    },
+    Button {
+        x: EDIT_HINT_OFFSET_X + 42,
+        y: EDIT_MAP_OFFSET_Y + 1,
+        width: 7,
+        height: 0,
+        return_code: EDIT_GENERATE
+    }
+];
+const EDIT_BUTTONS:[Button;3] = [
+    Button {
+        x: EDIT_HINT_OFFSET_X,
+        y: EDIT_HINT_OFFSET_Y + 9,
+        width: 6,
+        height: 2,
+        return_code: EDIT_RESET
+    },
+    Button {
+        x: EDIT_HINT_OFFSET_X + 8,
+        y: EDIT_HINT_OFFSET_Y + 9,
+        width: 5,
+        height: 2,
+        return_code: EDIT_UNDO
+    },
+    Button {
+        x: EDIT_HINT_OFFSET_X + 15,
+        y: EDIT_HINT_OFFSET_Y + 9,
+        width: 5,
+        height: 2,
+        return_code: EDIT_REDO
+    }
+];
+const MAINMENU_BUTTONS:[Button;10] = [
     Button {
         x: BUTTONS_POS.x,
         y: BUTTONS_POS.y,
@@ -234,6 +367,55 @@ const MAINMENU_BUTTONS:[Button;3] = [
         width: 10,
         height: 3,
         return_code: BUTTON_EXIT
+    },
+    Button {
+        x: BUTTONS_POS.x+13,
+        y: BUTTONS_POS.y,
+        width: 10,
+        height: 3,
+        return_code: BUTTON_VERSUS
+    },
+    Button {
+        x: BUTTONS_POS.x+13,
+        y: BUTTONS_POS.y+5,
+        width: 10,
+        height: 3,
+        return_code: BUTTON_MAPS
+    },
+    Button {
+        x: BUTTONS_POS.x+13,
+        y: BUTTONS_POS.y+10,
+        width: 10,
+        height: 3,
+        return_code: BUTTON_CAMPAIGN
+    },
+    Button {
+        x: BUTTONS_POS.x+26,
+        y: BUTTONS_POS.y,
+        width: 10,
+        height: 3,
+        return_code: BUTTON_MODES
+    },
+    Button {
+        x: BUTTONS_POS.x+26,
+        y: BUTTONS_POS.y+5,
+        width: 10,
+        height: 3,
+        return_code: BUTTON_PUZZLES
+    },
+    Button {
+        x: BUTTONS_POS.x+26,
+        y: BUTTONS_POS.y+10,
+        width: 10,
+        height: 3,
+        return_code: BUTTON_DAILY
+    },
+    Button {
+        x: BUTTONS_POS.x+39,
+        y: BUTTONS_POS.y,
+        width: 10,
+        height: 3,
+        return_code: BUTTON_SETTINGS
     }
 ];
 const GAME_FIELD_OFFSET_X:u16 = GLOBAL_OFFSET_X;
@@ -241,33 +423,144 @@ const GAME_FIELD_OFFSET_Y:u16 = 3;
 const EDIT_HINT_OFFSET_X:u16 = 30;
 const EDIT_HINT_OFFSET_Y:u16 = GLOBAL_OFFSET_Y;
 const EDIT_HINT_SIZE_X:u16 = 52;
-const EDIT_HINT_SIZE_Y:u16 = 11;
+const EDIT_HINT_SIZE_Y:u16 = 12;
+const EDIT_PALETTE_OFFSET_Y:u16 = EDIT_HINT_OFFSET_Y + EDIT_HINT_SIZE_Y + 1;
+const EDIT_MAP_OFFSET_Y:u16 = EDIT_PALETTE_OFFSET_Y + 4; //map name, its commands and their questions
 const SNAKE_SPAWN_POS_X:u16 = 2;
 const SNAKE_SPAWN_POS_Y:u16 = 2;
 const GAME_TIME_OFFSET:u16 = GLOBAL_OFFSET_X + 34;
 const GAME_SCORE_OFFSET:u16 = GLOBAL_OFFSET_Y + 7;
+const GAME_INFO_X:u16 = 27; //right of the field, where "Press any key" is
 const GAME_FIELD_SIZE:usize = 20;
-const APPLE:char = '¤'; //this sign is supportable with all fonts
-const COLOR_GRAY :Color = Color::Rgb{r:40,g:40,b:40};
+const SPAWN_SIGN:char = '·'; //what spawn and preview cells of the editor are, when there are no colors to tell them
+const PREVIEW_SIGN:char = '▒';
 const COLOR_RESET :Color = Color::Reset;
 const COLOR_WHITE :Color = Color::White;
-const COLOR_BLUE :Color = Color::Blue;
-const COLOR_YELLOW :Color = Color::Yellow;
-const COLOR_RED :Color = Color::Red;
-const COLOR_GREEN :Color = Color::Green;
+const MAX_PLAYERS:usize = 4; //one for every corner
+const ROUND_HINTS:[&str;2] = ["ENTER - again","ESC - menu"];
 //edit screen hint is the widest part and main menu buttons are the tallest part
 const SCREEN_MIN_SIZE_X:u16 = EDIT_HINT_OFFSET_X + EDIT_HINT_SIZE_X;
 const SCREEN_MIN_SIZE_Y:u16 = BUTTONS_POS.y+16;
 
-fn draw(cursor:&Cursor,color:Color) {
+fn draw(cursor:&Cursor,sign:StyledContent<char>) {
     queue!(
         stdout(),
         MoveTo(cursor.x,cursor.y),
-        Print(" ".on(color)),
+        Print(sign),
     ).handle();
 }
 
-fn back_to_main_menu(buttons_ascii:&String) {
+fn draw_editor(edit_screen:&str,map:&Map,map_name:&str,unsaved:bool,palette:&Palette,theme:&Theme) {
+    queue!(stdout(),Clear(ClearType::All)).handle();
+    draw_simple_ascii_picture(edit_screen,EDIT_HINT_OFFSET_X,EDIT_HINT_OFFSET_Y);
+    palette.draw(theme);
+    draw_map_commands(map_name,unsaved,map.spawn.players(&map.walls),theme);
+    draw_map(&map.walls,GLOBAL_OFFSET_X,GLOBAL_OFFSET_Y,theme);
+    draw_obstacles(&map.obstacles,GLOBAL_OFFSET_X,GLOBAL_OFFSET_Y,theme);
+    draw_spawns(&map.spawn,GLOBAL_OFFSET_X,GLOBAL_OFFSET_Y,theme);
+    stdout().flush().handle();
+}
+
+fn draw_map_commands(map_name:&str,unsaved:bool,players:usize,theme:&Theme) {
+    queue!(
+        stdout(),
+        MoveTo(EDIT_HINT_OFFSET_X,EDIT_MAP_OFFSET_Y),
+        Clear(ClearType::UntilNewLine),
+        Print(format!("Map: {}",map_name)),
+        Print(if unsaved {" (unsaved)".with(theme.bad)} else {"".reset()}),
+        Print(if players < MAX_PLAYERS {format!(" {} of {} players fit",players,MAX_PLAYERS).with(theme.bad)} else {"".to_string().reset()})
+    ).handle();
+    for (button,label) in EDIT_MAP_BUTTONS.iter().zip(EDIT_MAP_LABELS) {
+        queue!(stdout(),MoveTo(button.x,button.y),Print(label)).handle();
+    }
+}
+
+//asks for a name no other map has, explains what's wrong with it otherwise
+fn ask_map_name(question:&str,initial:&str,theme:&Theme) -> Option<String> {
+    let answer = prompt(EDIT_HINT_OFFSET_X,EDIT_MAP_OFFSET_Y + 2,question,initial)?;
+    let problem = match clean_map_name(&answer) {
+        Some(name) if !map_exists(&name) => return Some(name),
+        Some(name) => format!("Map \"{}\" already exists",name),
+        None => "Only letters, digits, - and _ please".to_string()
+    };
+    queue!(stdout(),MoveTo(EDIT_HINT_OFFSET_X,EDIT_MAP_OFFSET_Y + 2),Print(problem.with(theme.bad))).handle();
+    None
+}
+
+//asks how to generate the map, keeps the spawn where it is
+fn ask_generated_map(spawn:&Spawn,theme:&Theme) -> Option<Map> {
+    let initial = format!("{} {} {}",STYLES[0],thread_rng().gen::<u32>(),DENSITY);
+    let answer = prompt(EDIT_HINT_OFFSET_X,EDIT_MAP_OFFSET_Y + 2,"Style, seed, density:",&initial)?;
+    let words = answer.split_whitespace().collect::<Vec<&str>>();
+    let seed = words.get(1).and_then(|seed| seed.parse().ok()).unwrap_or_else(|| thread_rng().gen());
+    let density = words.get(2).and_then(|density| density.parse().ok()).unwrap_or(DENSITY);
+    let map = generate(words.first().unwrap_or(&STYLES[0]),seed,density,spawn.clone());
+    if map.is_none() {
+        let problem = format!("Styles: {}",STYLES.join(", "));
+        queue!(stdout(),MoveTo(EDIT_HINT_OFFSET_X,EDIT_MAP_OFFSET_Y + 2),Print(problem.with(theme.bad))).handle();
+    }
+    map
+}
+
+//SAVE, NEW, SAVE AS, RENAME, COPY and DELETE buttons of the editor, tells if another map was opened
+//`saved` is the text of the map file, whatever differs from it is unsaved
+fn map_command(command:u8,map:&mut Map,map_name:&mut String,saved:&mut String,theme:&Theme) -> bool {
+    match command {
+        EDIT_SAVE => {
+            *saved = save_map(map,map_name);
+            false
+        },
+        EDIT_NEW => {
+            let Some(name) = ask_map_name("New map name:","",theme) else {return false};
+            keep_unsaved(map,map_name,saved);
+            *map = Map::new();
+            *saved = save_map(map,&name);
+            *map_name = name;
+            true
+        },
+        EDIT_SAVE_AS => { //the old file stays as it was saved last time
+            let Some(name) = ask_map_name("Save as:",map_name,theme) else {return false};
+            forget_recovery(map_name);
+            *saved = save_map(map,&name);
+            *map_name = name;
+            false
+        },
+        EDIT_RENAME => {
+            let Some(name) = ask_map_name("Rename to:",map_name,theme) else {return false};
+            if rename_map(map_name,&name).is_err() {
+                *saved = save_map(map,&name); //wasn't saved yet
+            }
+            *map_name = name;
+            false
+        },
+        EDIT_COPY => { //keeps editing the original
+            let Some(name) = ask_map_name("Copy to:",&format!("{}_copy",map_name),theme) else {return false};
+            map.save(&map_path(&name));
+            false
+        },
+        EDIT_DELETE => {
+            let question = format!("Delete \"{}\"? y/n:",map_name);
+            let Some(answer) = prompt(EDIT_HINT_OFFSET_X,EDIT_MAP_OFFSET_Y + 2,&question,"") else {return false};
+            if !answer.eq_ignore_ascii_case("y") {return false}
+            delete_map(map_name);
+            match list_maps().into_iter().next() {
+                Some(name) => {
+                    (*map,*saved) = open_map(&name);
+                    *map_name = name;
+                },
+                None => { //the last one is gone, start from scratch
+                    *map = Map::new();
+                    *map_name = DEFAULT_MAP.to_string();
+                    *saved = save_map(map,map_name);
+                }
+            }
+            true
+        },
+        _ => false
+    }
+}
+
+fn back_to_main_menu(buttons_ascii:&str) {
     queue!(stdout(),Clear(ClearType::All)).handle();
     draw_simple_ascii_picture(buttons_ascii,BUTTONS_POS.x,BUTTONS_POS.y);
     stdout().flush().handle();
@@ -277,6 +570,12 @@ fn read_file(s:&str) -> String {
     fs::read_to_string(s).handle_read(s)
 }
 
+//ascii look of a picture is in the file with "_ascii" at the end, the rest get their glyphs swapped
+fn read_picture(file:&str,theme:&Theme) -> String {
+    if !theme.ascii {return read_file(file)}
+    fs::read_to_string(file.replace(".txt","_ascii.txt")).unwrap_or_else(|_| theme.text(&read_file(file)))
+}
+
 fn get_hover(posx:u16,posy:u16,button:&Button) -> u8 {
     if posx.checked_sub(button.x).unwrap_or(u16::MAX) <= button.width &&
         posy.checked_sub(button.y).unwrap_or(u16::MAX) <= button.height {
@@ -285,9 +584,50 @@ fn get_hover(posx:u16,posy:u16,button:&Button) -> u8 {
     NONE
 }
 
-fn draw_map(map:&[[bool;GAME_FIELD_SIZE];GAME_FIELD_SIZE],offsetx:u16,offsety:u16) {
-    let mut y = 0;
-    for line in map {
+//which way to go to get into the neighbour cell, edges included
+fn direction_between(from:&Pos,to:&Pos) -> Direction {
+    [Direction::Up,Direction::Right,Direction::Down,Direction::Left].into_iter()
+        .find(|dir| next_pos(from,dir) == *to)
+        .unwrap_or(Direction::Right)
+}
+
+//where you end up after one move, field edges are connected with each other
+fn next_pos(pos:&Pos,direction:&Direction) -> Pos {
+    let last = (GAME_FIELD_SIZE-1) as u16;
+    match direction {
+        Direction::Right => Pos {x: if pos.x < last {pos.x + 1} else {0}, y: pos.y},
+        Direction::Left => Pos {x: pos.x.checked_sub(1).unwrap_or(last), y: pos.y},
+        Direction::Up => Pos {x: pos.x, y: pos.y.checked_sub(1).unwrap_or(last)},
+        Direction::Down => Pos {x: pos.x, y: if pos.y < last {pos.y + 1} else {0}}
+    }
+}
+
+//quarter blocks by which quarters of the sign have walls: top left 1, top right 2, bottom left 4, bottom right 8
+const QUARTERS:[char;16] = [' ','▘','▝','▀','▖','▌','▞','▛','▗','▚','▐','▜','▄','▙','▟','█'];
+
+fn draw_map(map:&Walls,offsetx:u16,offsety:u16,theme:&Theme) {
+    draw_scaled_map(map,offsetx,offsety,1,theme);
+}
+
+//every sign shows scale x scale cells, anything smaller than the real size is drawn with quarter blocks
+fn draw_scaled_map(map:&Walls,offsetx:u16,offsety:u16,scale:usize,theme:&Theme) {
+    if scale > 1 {
+        let size = GAME_FIELD_SIZE.div_ceil(scale);
+        for y in 0..size {
+            queue!(stdout(),MoveTo(offsetx,offsety + y as u16)).handle();
+            for x in 0..size {
+                let mut index = 0;
+                for (dy,line) in map.iter().skip(y*scale).take(scale).enumerate() {
+                    for (dx,wall) in line.iter().skip(x*scale).take(scale).enumerate() {
+                        if *wall {index |= 1 << (dx*2/scale + 2*(dy*2/scale))}
+                    }
+                }
+                queue!(stdout(),Print(theme.sign(QUARTERS[index]).with(theme.wall_background).on(theme.field))).handle();
+            }
+        }
+        return
+    }
+    for (y,line) in (0u16..).zip(map) {
         queue!(
             stdout(),
             MoveTo(
@@ -298,30 +638,388 @@ fn draw_map(map:&[[bool;GAME_FIELD_SIZE];GAME_FIELD_SIZE],offsetx:u16,offsety:u1
         for row in line {
             queue!(
                 stdout(),
-                Print(" ".on(
-                    if row == &true {
-                        COLOR_YELLOW
+                Print(
+                    if *row {
+                        theme.wall_sign()
                     } else {
-                        COLOR_GRAY
+                        ' '.on(theme.field)
                     }
-                ))
+                )
             ).handle()
             
         }
-        y += 1;
     }
 }
 
-fn reset_map(map: &mut [[bool;GAME_FIELD_SIZE];GAME_FIELD_SIZE]) {
-    for y in 0..(GAME_FIELD_SIZE) {
-        if y == 0 || y == (GAME_FIELD_SIZE-1) {map[y] = [true;GAME_FIELD_SIZE];}
-        else {
-            map[y][0] = true;
-            map[y][(GAME_FIELD_SIZE-1)] = true;
+fn draw_obstacles(obstacles:&[Obstacle],offsetx:u16,offsety:u16,theme:&Theme) {
+    for obstacle in obstacles {
+        for cell in obstacle.cells() {
+            queue!(
+                stdout(),
+                MoveTo(
+                    cell.x + offsetx,
+                    cell.y + offsety
+                ),
+                Print(theme.obstacle_sign())
+            ).handle();
         }
     }
 }
 
+//puts back whatever is left in the cell after obstacle or dead snake went away
+fn erase_cells(cells:&[Pos],game:&Game,theme:&Theme) {
+    for cell in cells {
+        if game.obstacles.iter().any(|o| o.is_in_point(cell.x,cell.y)) ||
+            game.snakes.iter().any(|s| s.alive && s.is_in_point(cell.x,cell.y)) {continue}
+        let sign = if game.walls[cell.y as usize][cell.x as usize] {
+            theme.wall_sign()
+        } else if *cell == game.fruit.pos || game.leftovers.contains(cell) {
+            theme.fruit_sign()
+        } else {
+            ' '.on(theme.field)
+        };
+        queue!(
+            stdout(),
+            MoveTo(
+                cell.x + GAME_FIELD_OFFSET_X,
+                cell.y + GAME_FIELD_OFFSET_Y
+            ),
+            Print(sign)
+        ).handle();
+    }
+}
+
+fn player_color(player:usize,theme:&Theme) -> Color {
+    theme.players[player % theme.players.len()]
+}
+
+//first snake's cells, editor doesn't let you build walls there even when nobody else fits
+fn is_spawn(spawn:&Spawn,x:u16,y:u16) -> bool {
+    Snake::player(spawn,0).is_in_point(x,y)
+}
+
+fn draw_spawns(spawn:&Spawn,offsetx:u16,offsety:u16,theme:&Theme) {
+    for player in 0..MAX_PLAYERS {
+        let snake = Snake::player(spawn,player);
+        for part in &snake.body {
+            queue!(
+                stdout(),
+                MoveTo(part.x + offsetx,part.y + offsety),
+                Print(theme.block(theme.spawn,SPAWN_SIGN))
+            ).handle();
+        }
+        queue!( //arrow shows where snake will go first
+            stdout(),
+            MoveTo(snake.pos.x + offsetx,snake.pos.y + offsety),
+            Print(theme.player(player).head(&snake.direction).with(theme.cursor).on(theme.spawn))
+        ).handle();
+    }
+}
+
+//one score for single game, "p1 : p2" in versus
+fn draw_scores(snakes:&[Snake],theme:&Theme) {
+    queue!(stdout(),MoveTo(GAME_SCORE_OFFSET,GLOBAL_OFFSET_Y)).handle();
+    for (player,snake) in snakes.iter().enumerate() {
+        if player > 0 {
+            queue!(stdout(),Print(" : ")).handle();
+        }
+        queue!(
+            stdout(),
+            Print(snake.score.to_string().with(
+                if snakes.len() > 1 {player_color(player,theme)} else {COLOR_RESET}
+            ))
+        ).handle();
+    }
+}
+
+fn start_game(game_field:&str,map:&Map,snakes:usize,rules:Rules,theme:&Theme) -> Game {
+    start_seeded_game(game_field,map,snakes,rules,thread_rng().gen(),theme)
+}
+
+//the same seed puts the fruit in the same places, as long as snakes go the same way
+fn start_seeded_game(game_field:&str,map:&Map,snakes:usize,rules:Rules,seed:u64,theme:&Theme) -> Game {
+    execute!(
+        stdout(),
+        Clear(ClearType::All)
+    ).handle();
+    draw_simple_ascii_picture(game_field,1,1);
+    draw_map(&map.walls,GAME_FIELD_OFFSET_X,GAME_FIELD_OFFSET_Y,theme);
+    draw_obstacles(&map.obstacles,GAME_FIELD_OFFSET_X,GAME_FIELD_OFFSET_Y,theme);
+    stdout().flush().handle();
+    let mut game = Game::new(map,seed,snakes);
+    game.rules = rules;
+    game.scatter();
+    for (player,snake) in game.snakes.iter().enumerate() {
+        let color = player_color(player,theme);
+        let signs = theme.player(player);
+        queue!(
+            stdout(),
+            SetBackgroundColor(theme.field),
+            MoveTo(
+                snake.pos.x + GAME_FIELD_OFFSET_X,
+                snake.pos.y + GAME_FIELD_OFFSET_Y
+            ),
+            Print(signs.head(&snake.direction).with(color))
+        ).handle();
+        for body in &snake.body {
+            queue!(
+                stdout(),
+                MoveTo(
+                    body.x + GAME_FIELD_OFFSET_X,
+                    body.y + GAME_FIELD_OFFSET_Y
+                ),
+                Print(signs.body[0].with(color))
+            ).handle();
+        }
+    }
+    for fruit in [&game.fruit.pos].into_iter().chain(&game.leftovers) {
+        queue!(
+            stdout(),
+            MoveTo(
+                fruit.x + GAME_FIELD_OFFSET_X,
+                fruit.y + GAME_FIELD_OFFSET_Y
+            ),
+            Print(theme.fruit_sign())
+        ).handle();
+    }
+    queue!(stdout(),SetBackgroundColor(COLOR_RESET)).handle();
+    draw_scores(&game.snakes,theme);
+    queue!(
+        stdout(),
+        MoveTo(GAME_TIME_OFFSET,GLOBAL_OFFSET_Y),
+        Print(0)
+    ).handle();
+    stdout().flush().handle();
+    game
+}
+
+//graphics of snake rotation
+fn body_sign(came:&Direction,going:&Direction,theme:&Theme) -> char {
+    match came {
+        Direction::Right => { //formula: invert prev_move and copy cur dir
+            match going {
+                Direction::Right | Direction::Left => theme.body[0],
+                Direction::Up => theme.body[5],
+                Direction::Down => theme.body[3]
+            }
+        },
+        Direction::Left => {
+            match going {
+                Direction::Right | Direction::Left => theme.body[0],
+                Direction::Up => theme.body[4],
+                Direction::Down => theme.body[2]
+            }
+        },
+        Direction::Up => {
+            match going {
+                Direction::Right => theme.body[2],
+                Direction::Left => theme.body[3],
+                Direction::Up | Direction::Down => theme.body[1]
+            }
+        },
+        Direction::Down => {
+            match going {
+                Direction::Right => theme.body[4],
+                Direction::Left => theme.body[5],
+                Direction::Up | Direction::Down => theme.body[1]
+            }
+        }
+    }
+}
+
+//redraws only cells that changed during the tick
+fn draw_step(game:&Game,step:&Step,theme:&Theme) {
+    //tails go first, so the other snake's head doesn't get erased when it takes the cell
+    for tail in step.tails.iter().flatten() {
+        if game.snakes.iter().any(|snake| snake.is_in_point(tail.x,tail.y)) {continue} //zen snakes cross themselves
+        queue!(
+            stdout(),
+            SetBackgroundColor(theme.field),
+            MoveTo(
+                tail.x + GAME_FIELD_OFFSET_X,
+                tail.y + GAME_FIELD_OFFSET_Y
+            ),
+            Print(' ')
+        ).handle();
+    }
+    for (player,snake) in game.snakes.iter().enumerate() {
+        //died earlier and doesn't move anymore, or vanished right away
+        if !snake.alive && (step.deaths[player].is_none() || snake.body.is_empty()) {continue}
+        let prev_move = &step.prev_moves[player];
+        queue!(
+            stdout(),
+            SetBackgroundColor(theme.field),
+            MoveTo(
+                snake.pos.x + GAME_FIELD_OFFSET_X,
+                snake.pos.y + GAME_FIELD_OFFSET_Y
+            ),
+            SetForegroundColor(player_color(player,theme)),
+            Print(theme.player(player).head(&snake.direction)),
+            MoveTo(
+                snake.body.back().unwrap().x + GAME_FIELD_OFFSET_X,
+                snake.body.back().unwrap().y + GAME_FIELD_OFFSET_Y
+            ),
+            Print(body_sign(prev_move,&snake.direction,&theme.player(player))),
+            SetBackgroundColor(COLOR_RESET),
+            SetForegroundColor(COLOR_RESET)
+        ).handle();
+    }
+    if step.ate.contains(&true) {
+        draw_scores(&game.snakes,theme);
+    }
+    //the last of the scattered fruit is left under the head that ate it
+    if step.ate.contains(&true) && !game.snakes.iter().any(|snake| snake.pos == game.fruit.pos) {
+        queue!(
+            stdout(),
+            MoveTo(game.fruit.pos.x+GAME_FIELD_OFFSET_X,game.fruit.pos.y+GAME_FIELD_OFFSET_Y),
+            Print(theme.fruit_sign()),
+        ).handle();
+    }
+    erase_cells(&step.removed,game,theme);
+    erase_cells(&step.obstacle_cells,game,theme);
+    //border goes over everything, fruit that was there has moved
+    for cell in &step.walls_added {
+        queue!(
+            stdout(),
+            MoveTo(cell.x + GAME_FIELD_OFFSET_X,cell.y + GAME_FIELD_OFFSET_Y),
+            Print(theme.wall_sign())
+        ).handle();
+    }
+    if !step.walls_added.is_empty() {
+        queue!(
+            stdout(),
+            MoveTo(game.fruit.pos.x+GAME_FIELD_OFFSET_X,game.fruit.pos.y+GAME_FIELD_OFFSET_Y),
+            Print(theme.fruit_sign())
+        ).handle();
+    }
+    draw_obstacles(&game.obstacles,GAME_FIELD_OFFSET_X,GAME_FIELD_OFFSET_Y,theme);
+    queue!(stdout(),SetBackgroundColor(COLOR_RESET)).handle();
+}
+
+//every cell of the snake from tail to head with its sign, when only cells are known and not the moves
+fn snake_signs(snake:&Snake,theme:&Theme) -> Vec<(Pos,char)> {
+    if !snake.alive && snake.body.is_empty() {return vec![]} //vanished
+    let cells = snake.body.iter().chain([&snake.pos]).collect::<Vec<&Pos>>();
+    cells.iter().enumerate().map(|(i,cell)| {
+        let sign = match (i.checked_sub(1),cells.get(i + 1)) {
+            (_,None) => theme.head(&snake.direction),
+            (None,Some(next)) => body_sign(&direction_between(cell,next),&direction_between(cell,next),theme),
+            (Some(prev),Some(next)) => body_sign(&direction_between(cells[prev],cell),&direction_between(cell,next),theme)
+        };
+        (**cell,sign)
+    }).collect()
+}
+
+//draws everything from scratch, for screens that only know the state and not how it changed
+fn draw_state(walls:&Walls,state:&State,theme:&Theme) {
+    draw_map(walls,GAME_FIELD_OFFSET_X,GAME_FIELD_OFFSET_Y,theme);
+    queue!(stdout(),SetBackgroundColor(theme.field)).handle();
+    for cell in &state.obstacles {
+        queue!(
+            stdout(),
+            MoveTo(cell.x + GAME_FIELD_OFFSET_X,cell.y + GAME_FIELD_OFFSET_Y),
+            Print(theme.obstacle_sign())
+        ).handle();
+    }
+    for fruit in &state.fruits {
+        queue!(
+            stdout(),
+            MoveTo(fruit.x + GAME_FIELD_OFFSET_X,fruit.y + GAME_FIELD_OFFSET_Y),
+            Print(theme.fruit_sign())
+        ).handle();
+    }
+    for (player,snake) in state.snakes.iter().enumerate() {
+        for (cell,sign) in snake_signs(snake,&theme.player(player)) {
+            queue!(
+                stdout(),
+                MoveTo(cell.x + GAME_FIELD_OFFSET_X,cell.y + GAME_FIELD_OFFSET_Y),
+                Print(sign.with(player_color(player,theme)))
+            ).handle();
+        }
+    }
+    queue!(stdout(),SetBackgroundColor(COLOR_RESET)).handle();
+    draw_scores(&state.snakes,theme);
+    queue!(
+        stdout(),
+        MoveTo(GAME_TIME_OFFSET,GLOBAL_OFFSET_Y),
+        Print(state.tick as u64 * GAME_TICK / 1000)
+    ).handle();
+}
+
+//box in the middle of the field telling who won the round
+fn draw_round_over(winner:Option<usize>,wins:&[u16],hints:&[&str;2],theme:&Theme) {
+    let title = match winner {
+        Some(player) => format!("PLAYER {} WINS!",player + 1).with(player_color(player,theme)),
+        None if wins.len() < 2 => "GAME OVER!".to_string().with(theme.bad), //there was nobody to win against
+        None => "DRAW!".to_string().with(theme.draw)
+    };
+    let rounds = wins.iter().map(|w| w.to_string()).collect::<Vec<String>>()
+        .join(if wins.len() > 2 {":"} else {" : "});
+    draw_box(title,&[format!("rounds {}",rounds),hints[0].to_string(),hints[1].to_string()],theme);
+}
+
+//box in the middle of the field with a title and 3 lines under it
+fn draw_box(title:StyledContent<String>,lines:&[String;3],theme:&Theme) {
+    let lines = [String::new()].into_iter().chain(lines.iter().cloned()).map(|line| theme.text(&format!("│ {:<16} │",line)));
+    let y = GAME_FIELD_OFFSET_Y + 7;
+    queue!(
+        stdout(),
+        SetBackgroundColor(COLOR_RESET),
+        MoveTo(GAME_FIELD_OFFSET_X,y),
+        Print(theme.text("┌──────────────────┐"))
+    ).handle();
+    for (i,line) in (1u16..).zip(lines) {
+        queue!(stdout(),MoveTo(GAME_FIELD_OFFSET_X,y + i),Print(line)).handle();
+    }
+    let padding = 16 - title.content().chars().count();
+    queue!(
+        stdout(),
+        MoveTo(GAME_FIELD_OFFSET_X + 2 + padding as u16 / 2,y + 1),
+        Print(title),
+        MoveTo(GAME_FIELD_OFFSET_X,y + 5),
+        Print(theme.text("└──────────────────┘"))
+    ).handle();
+}
+
+fn draw_demo_hint() {
+    execute!(stdout(),MoveTo(27,3),Clear(ClearType::UntilNewLine),Print("Demo, press any key")).handle();
+}
+
+//names for the replay, bot plays under its own name and computer rivals go after people
+fn local_names(players:usize,snakes:usize,bot:Option<&str>) -> Vec<String> {
+    let bot = bot.and_then(clean_name).filter(|_| players == 1);
+    (0..snakes).map(|i| match &bot {
+        Some(name) if i == 0 => name.clone(),
+        _ if i < players => default_name(i),
+        _ => format!("Rival_{}",i + 1 - players)
+    }).collect()
+}
+
+fn wait_for_start() {
+    loop {
+        if let Event::Key(_) = read().expect("Can't detect pressed key") {
+            break;
+        }
+    }
+}
+
+//true for ENTER, false for ESC
+fn confirm() -> bool {
+    stdout().flush().handle();
+    loop {
+        match idle_input(Duration::MAX) {
+            InputResult::Click => return true,
+            InputResult::Abort => return false,
+            _ => ()
+        }
+    }
+}
+
+//game time, not the real one, so pauses between ticks don't count
+fn game_seconds(game:&Game) -> u32 {
+    (game.tick as u64 * GAME_TICK / 1000) as u32
+}
+
 pub fn free_window(w:u16,h:u16) {
     disable_raw_mode().handle();
     if w+h != 0 {
@@ -339,12 +1037,122 @@ pub fn free_window(w:u16,h:u16) {
     ).handle();
 }
 
+fn flag(name:&str) -> bool {
+    std::env::args().any(|arg| arg == name)
+}
+
+fn option(name:&str) -> Option<String> { //value that goes after the name
+    let mut args = std::env::args();
+    args.find(|arg| arg == name)?;
+    args.next()
+}
+
 fn max(first_num:u16,second_num:u16) -> u16 {
     if first_num < second_num {second_num}
     else {first_num}
 }
 
 fn main() {
+
+    //subcommands that don't need the screen at all
+    match std::env::args().nth(1).as_deref() {
+        Some("gym") => return gym::run(),
+        Some("bench") => return bench::run(),
+        Some("generate") => return generator::run(),
+        _ => ()
+    }
+
+    //bot steers the snake if asked, --autopilot is a shortcut for the built-in one
+    let bot_name = option("--bot").or(if flag("--autopilot") {Some("autopilot".to_string())} else {None});
+    let bot_timeout = option("--bot-timeout").and_then(|ms| ms.parse().ok()).unwrap_or(BOT_TIMEOUT);
+    let mut controller:Option<Box<dyn Controller>> = match (&bot_name,option("--bot-cmd")) {
+        (_,Some(command)) => match ExternalBot::spawn(&command,Duration::from_millis(bot_timeout)) {
+            Ok(bot) => Some(Box::new(bot)),
+            Err(error) => {
+                eprintln!("Can't start bot \"{}\": {}",command,error);
+                return
+            }
+        },
+        (Some(name),None) => match bot_by_name(name) {
+            Some(bot) => Some(bot),
+            None => {
+                eprintln!("Unknown bot \"{}\", available bots: {}",name,BOTS.join(", "));
+                return
+            }
+        },
+        (None,None) => None
+    };
+
+    //computer snakes that play against you in a single game
+    let rival_count = option("--rivals").and_then(|n| n.parse().ok()).unwrap_or(0usize).min(MAX_PLAYERS - 1);
+    let skill = option("--rival-skill").unwrap_or("normal".to_string());
+    let mut rivals = vec![];
+    for _ in 0..rival_count {
+        match rival_by_skill(&skill) {
+            Some(rival) => rivals.push(rival),
+            None => {
+                eprintln!("Unknown skill \"{}\", available skills: {}",skill,SKILLS.join(", "));
+                return
+            }
+        }
+    }
+    let rival_corpses = if flag("--rival-fruit") {Corpses::Fruit} else {Corpses::Vanish};
+    //battle royale, field gets smaller until only one snake is left
+    let shrink = option("--shrink").and_then(|n| n.parse().ok())
+        .unwrap_or(if flag("--royale") {SHRINK_TICKS} else {0});
+
+    //every game can be written to a file, so somebody can watch it with --watch-file
+    let mut recorder = match option("--record") {
+        Some(file) => match Recorder::create(&file) {
+            Ok(recorder) => Some(recorder),
+            Err(error) => {
+                eprintln!("Can't create \"{}\": {}",file,error);
+                return
+            }
+        },
+        None => None
+    };
+    let bot_label = if option("--bot-cmd").is_some() {Some("bot".to_string())} else {bot_name.clone()};
+
+    //network game, hosting terminal plays too
+    let name = option("--name");
+    let server = if flag("--host") {
+        let port = option("--port").and_then(|port| port.parse().ok()).unwrap_or(PORT);
+        let address = option("--bind").unwrap_or(ANY_ADDRESS.to_string());
+        let host_name = name.as_deref().and_then(clean_name).unwrap_or(default_name(0));
+        match Server::bind(&address,port,host_name,recorder.take()) {
+            Ok(server) => Some(server),
+            Err(error) => {
+                eprintln!("Can't host a game on {}:{}: {}",address,port,error);
+                return
+            }
+        }
+    } else {None};
+    //spectators connect just like players, but never get a snake
+    let (address,spectator) = match (option("--join"),option("--watch")) {
+        (Some(address),_) => (Some(address),false),
+        (None,address) => (address,true)
+    };
+    let connection = match address {
+        Some(address) => match Connection::connect(&address,if spectator {None} else {Some(name.as_deref().unwrap_or_default())}) {
+            Ok(connection) => Some(connection),
+            Err(error) => {
+                eprintln!("Can't join \"{}\": {}",address,error);
+                return
+            }
+        },
+        None => None
+    };
+    let replay = match option("--watch-file") {
+        Some(file) => match tail(&file) {
+            Ok(lines) => Some(lines),
+            Err(error) => {
+                eprintln!("Can't open \"{}\": {}",file,error);
+                return
+            }
+        },
+        None => None
+    };
     
     //Setup window
     let (term_old_w,term_old_h) = size().unwrap();
@@ -364,13 +1172,17 @@ fn main() {
 
     let mut form = Screen::MainMenu;
 
+    let saved = Settings::load();
+    let mut theme = Theme::load(&option("--theme").unwrap_or(saved.theme.clone()),saved.ascii(),saved.colors()); //--theme is only for this time, settings keep theirs
+
     //reading files
-    let title = read_file(FILE_TITLE); //title ascii picture
-    let buttons_ascii = read_file(FILE_BUTTONS); //main menu buttons ascii
+    let mut title = read_picture(FILE_TITLE,&theme); //title ascii picture
+    let mut buttons_ascii = read_picture(FILE_BUTTONS,&theme); //main menu buttons ascii
     let title_colors = read_file(FILE_TITLECOLORS); //colors for title
     let game_field = read_file(FILE_GAME); //game scene
-    let edit_screen = read_file(FILE_EDITOR); //field edit scene
-    let map_string = fs::read_to_string("map.txt"); // saved map 
+    let mut edit_screen = read_picture(FILE_EDITOR,&theme); //field edit scene
+    let mut map_name = starting_map();
+    let (mut map,mut saved_map) = open_map(&map_name); // saved map and how it looks in the file
     
     let title_colors_size = title_colors.lines().count();
     let mut title_colors_iter:usize = 0;
@@ -391,31 +1203,37 @@ fn main() {
         hover: NONE
     };
 
-    let mut map = [[false;GAME_FIELD_SIZE];GAME_FIELD_SIZE];
-    
-    if let Ok(s) = map_string { //if life gives you files - read them
-        let mut y = 0;
-        let mut x = 0;
-        let mut file_map_lines = s.lines();
-        while let Some(line) = file_map_lines.next() {
-            if y >= GAME_FIELD_SIZE {break;}
-            let mut chars = line.chars();
-            while let Some(ch) = chars.next() {
-                if x >= GAME_FIELD_SIZE {break;}
-                map[y][x] = if ch == '1' {true} else {false};
-                x += 1;
-            }
-            x = 0;
-            y += 1;
-        }
-    } else {
-        reset_map(&mut map);
+    if let Some(server) = server {
+        net::host(server,&game_field,&map,shrink,&theme);
+        free_window(term_old_w,term_old_h);
+        return
+    }
+    if let Some(connection) = connection {
+        net::join(connection,&game_field,&theme);
+        free_window(term_old_w,term_old_h);
+        return
+    }
+    if let Some(lines) = replay {
+        net::watch_file(lines,&game_field,&theme);
+        free_window(term_old_w,term_old_h);
+        return
     }
 
-    let mut snake = Snake::new();
-    let mut fruit = Fruit::new(&map);
-    let mut score = 0u16;
+    let mut game = Game::new(&map,0,1);
     let mut time = SystemTime::now();
+    let mut dead_ticks = 0u16;
+    let mut players = 1; //2 in versus
+    let mut snakes = 1; //players and rivals
+    let mut rules = Rules::default();
+    let rounds = |players:usize,rules:&Rules| players > 1 || rules.shrink > 0; //somebody has to win, not just die
+    let mut demo = false; //autopilot plays because nobody touched the main menu for a while
+    let mut demo_bot:Box<dyn Controller> = Box::new(Autopilot::default());
+    let mut idle = SystemTime::now();
+    let mut wins = vec![]; //rounds won by each snake
+    let mut history:History<Map> = History::new(); //editor changes
+    let mut dragging = false; //the whole drag is one step of history, true once it has one
+    let mut palette = Palette::new(EDIT_HINT_OFFSET_X,EDIT_PALETTE_OFFSET_Y);
+    let mut grabbed:Option<Pos> = None; //spawn is being moved, cell it was picked up at
     
     draw_simple_ascii_picture(&buttons_ascii,BUTTONS_POS.x,BUTTONS_POS.y);
     stdout().flush().handle();
@@ -433,7 +1251,7 @@ fn main() {
                     tick = SystemTime::now();
                 }
                 //got rid of derefencing, but at what cost?
-                queue!(stdout(),SetForegroundColor(title_colors[title_colors_iter])).handle();
+                queue!(stdout(),SetForegroundColor(theme.color(title_colors[title_colors_iter]))).handle();
                 draw_simple_ascii_picture(
                     &title,
                     GLOBAL_OFFSET_X,
@@ -442,120 +1260,98 @@ fn main() {
                 queue!(stdout(),SetForegroundColor(COLOR_RESET)).handle();
                 stdout().flush().handle();
 
-                draw(&cursor,COLOR_RESET);
+                draw(&cursor,' '.on(COLOR_RESET));
                 
                 if cursor.hover != NONE {
                     draw_simple_ascii_picture(&buttons_ascii,BUTTONS_POS.x,BUTTONS_POS.y);
                 }
                 
+                let last_position = (cursor.x,cursor.y);
                 let input_result = cursor_input(
                     &mut cursor,Duration::from_millis(
-                        GAME_TICK.checked_sub(menu_elapsed as u64).unwrap_or(0)
+                        GAME_TICK.saturating_sub(menu_elapsed as u64)
                     )
                 );
                 
-                draw(
-                    &cursor,
-                    if cursor.hover == NONE {
-                        COLOR_WHITE
-                    } else {
-                        COLOR_BLUE
-                    }
-                );
+                draw(&cursor,theme.cursor_sign(cursor.hover != NONE));
 
                 stdout().flush().handle();
 
                 match input_result {
                     InputResult::Abort => break,
                     InputResult::Click => match cursor.hover {
-                        BUTTON_PLAY => {
-                            form = Screen::Game;
-                            execute!(
-                                stdout(),
-                                Clear(ClearType::All)
-                            ).handle();
-                            draw_simple_ascii_picture(&game_field,1,1);
-                            draw_map(&map,GAME_FIELD_OFFSET_X,GAME_FIELD_OFFSET_Y);
-                            stdout().flush().handle();
-                            snake = Snake::new();
-                            let tail = snake.body.front().unwrap();
-                            execute!(
+                        BUTTON_VERSUS if map.spawn.players(&map.walls) < 2 => {
+                            queue!(
                                 stdout(),
-                                SetBackgroundColor(COLOR_GRAY),
-                                MoveTo(
-                                    snake.pos.x + GAME_FIELD_OFFSET_X,
-                                    snake.pos.y + GAME_FIELD_OFFSET_Y
-                                ),
-                                Print('►'.green()),
-                                MoveTo(
-                                    tail.x + GAME_FIELD_OFFSET_X,
-                                    tail.y + GAME_FIELD_OFFSET_Y
-                                ),
-                                Print('═'.green()),
+                                MoveTo(BUTTONS_POS.x,BUTTONS_POS.y + buttons_ascii.lines().count() as u16 + 1),
+                                Clear(ClearType::UntilNewLine),
+                                Print(format!("VERSUS needs room for 2 snakes, this map has it for {}, move or turn its spawn in the editor",map.spawn.players(&map.walls)))
                             ).handle();
-                            for body in &snake.body {
-                                queue!(
-                                    stdout(),
-                                    SetBackgroundColor(COLOR_GRAY),
-                                    MoveTo(
-                                        body.x + GAME_FIELD_OFFSET_X,
-                                        body.y + GAME_FIELD_OFFSET_Y
-                                    ),
-                                    Print('═'.green())
-                                ).handle();
+                        },
+                        BUTTON_PLAY | BUTTON_VERSUS => {
+                            form = Screen::Game;
+                            players = if cursor.hover == BUTTON_VERSUS {2} else {1};
+                            (snakes,rules.corpses) = if players == 1 && !rivals.is_empty() {
+                                (1 + rivals.len(),rival_corpses)
+                            } else {
+                                (players,Corpses::Stay) //in versus the round is over anyway
+                            };
+                            rules.shrink = shrink;
+                            wins = vec![0;snakes];
+                            game = start_game(&game_field,&map,snakes,rules,&theme);
+                            if let Some(recorder) = &mut recorder {
+                                recorder.round(&game,&local_names(players,snakes,bot_label.as_deref()));
                             }
-                            fruit.respawn(&map,&snake);
-                            queue!(
+                            if controller.is_none() || players > 1 {
+                                wait_for_start();
+                            }
+                            execute!(
                                 stdout(),
-                                MoveTo(
-                                    fruit.pos.x + GAME_FIELD_OFFSET_X,
-                                    fruit.pos.y + GAME_FIELD_OFFSET_Y
-                                ),
-                                Print(APPLE.red()),
-                                SetBackgroundColor(COLOR_RESET),
-                                MoveTo(GAME_SCORE_OFFSET,GLOBAL_OFFSET_Y),
-                                Print(score),
-                                MoveTo(GAME_TIME_OFFSET,GLOBAL_OFFSET_Y),
-                                Print(0)
+                                MoveTo(27,3),
+                                Clear(ClearType::UntilNewLine)
                             ).handle();
-                            stdout().flush().handle();
-                            loop {
-                                if let Event::Key(_) = read().expect("Can't detect pressed key") {
-                                    execute!(
-                                        stdout(),
-                                        MoveTo(27,3),
-                                        Clear(ClearType::UntilNewLine)
-                                    ).handle();
-                                    break;
-                                }
-                            }
                             tick = SystemTime::now();
                             time = SystemTime::now();
                         },
                         BUTTON_EXIT => break,
                         BUTTON_EDIT => {
                             form = Screen::Edit;
-                            queue!(
-                                stdout(),
-                                Clear(ClearType::All)
-                            ).handle();
-                            draw_simple_ascii_picture(
-                                &edit_screen,
-                                EDIT_HINT_OFFSET_X,
-                                EDIT_HINT_OFFSET_Y
-                            );
-
-                            draw_map(&map,GLOBAL_OFFSET_X,GLOBAL_OFFSET_Y);
-                            queue!(
-                                stdout(),
-                                MoveTo(
-                                    GLOBAL_OFFSET_X + SNAKE_SPAWN_POS_X,
-                                    GLOBAL_OFFSET_Y + SNAKE_SPAWN_POS_Y,
-                                ),
-                                Print("   ".on_red())
-                            ).handle();
-                            stdout().flush().handle();
-                        }
+                            draw_editor(&edit_screen,&map,&map_name,map.to_text() != saved_map,&palette,&theme);
+                        },
+                        BUTTON_MAPS => {
+                            if let Some(name) = browse(&map_name,&theme) {
+                                if name != map_name { //edits of the old one are kept, but not saved
+                                    keep_unsaved(&map,&map_name,&saved_map);
+                                    (map,saved_map) = open_map(&name);
+                                    map_name = name;
+                                    history = History::new();
+                                }
+                            }
+                            back_to_main_menu(&buttons_ascii);
+                        },
+                        BUTTON_CAMPAIGN => {
+                            campaign::run(&game_field,&theme);
+                            back_to_main_menu(&buttons_ascii);
+                        },
+                        BUTTON_MODES => {
+                            modes::run(&game_field,&map,&map_name,&theme);
+                            back_to_main_menu(&buttons_ascii);
+                        },
+                        BUTTON_PUZZLES => {
+                            puzzle::run(&game_field,&theme);
+                            back_to_main_menu(&buttons_ascii);
+                        },
+                        BUTTON_DAILY => {
+                            daily::run(&game_field,&theme);
+                            back_to_main_menu(&buttons_ascii);
+                        },
+                        BUTTON_SETTINGS => {
+                            settings::run(&mut theme);
+                            title = read_picture(FILE_TITLE,&theme); //glyphs could be changed
+                            buttons_ascii = read_picture(FILE_BUTTONS,&theme);
+                            edit_screen = read_picture(FILE_EDITOR,&theme);
+                            back_to_main_menu(&buttons_ascii);
+                        },
                         _ => ()
                     },
                     _ => ()
@@ -563,171 +1359,136 @@ fn main() {
 
                 cursor.hover = NONE;
                 for button in &MAINMENU_BUTTONS {
-                    let code = get_hover(cursor.x,cursor.y,&button);
+                    let code = get_hover(cursor.x,cursor.y,button);
                     if code != NONE {
                         cursor.hover = code;
                         break
                     }
                 }
+
+                if !matches!(input_result,InputResult::Continue) || (cursor.x,cursor.y) != last_position {
+                    idle = SystemTime::now(); //screens opened from here count too, they are left only just now
+                } else if idle.elapsed().unwrap().as_millis() >= DEMO_IDLE as u128 {
+                    //the same game PLAY starts, only without rivals and shrinking, so it goes on until somebody comes
+                    demo = true;
+                    form = Screen::Game;
+                    (players,snakes) = (1,1);
+                    rules.corpses = Corpses::Stay;
+                    rules.shrink = 0;
+                    wins = vec![0];
+                    game = start_game(&game_field,&map,snakes,rules,&theme);
+                    draw_demo_hint();
+                    tick = SystemTime::now();
+                    time = SystemTime::now();
+                }
             },
             Screen::Game => {
                 let elapsed = tick.elapsed().unwrap().as_millis();
-                let input = game_input(
-                    &mut snake,
-                    Duration::from_millis(
-                        GAME_TICK.checked_sub(elapsed as u64).unwrap_or(0)
-                    )
-                );
-                if elapsed >= GAME_TICK as u128 {
-                    tick = SystemTime::now();
-                    if snake.alive == false {
+                let wait = Duration::from_millis(GAME_TICK.saturating_sub(elapsed as u64));
+                //bot only plays alone, versus is for humans
+                let bot = if demo {Some(&mut demo_bot)} else if players == 1 {controller.as_mut()} else {None};
+                let input = if demo {
+                    demo_input(wait)
+                } else if bot.is_some() {
+                    idle_input(wait)
+                } else {
+                    game_input(&mut game.snakes[..players],wait) //rivals can't be steered
+                };
+                let round_over = rounds(players,&rules) && game.alive() < snakes.min(2);
+                if round_over {
+                    if let InputResult::Click = input {
+                        game = start_game(&game_field,&map,snakes,rules,&theme);
+                        if let Some(recorder) = &mut recorder {
+                            recorder.round(&game,&local_names(players,snakes,bot_label.as_deref()));
+                        }
+                        if bot.is_none() {
+                            wait_for_start();
+                        }
                         execute!(
                             stdout(),
-                            SetForegroundColor(COLOR_RESET),
-                            MoveTo(29,3),
-                            Print("You died! Press ESC to return back to menu".red())
+                            MoveTo(27,3),
+                            Clear(ClearType::UntilNewLine)
                         ).handle();
-                        continue
+                        tick = SystemTime::now();
+                        time = SystemTime::now();
                     }
-                    snake.body.push_back(
-                        Pos {
-                            x:snake.pos.x,
-                            y:snake.pos.y
+                } else if elapsed >= GAME_TICK as u128 {
+                    tick = SystemTime::now();
+                    if !rounds(players,&rules) && !game.snakes[0].alive {
+                        if !demo {
+                            execute!(
+                                stdout(),
+                                SetForegroundColor(COLOR_RESET),
+                                MoveTo(29,3),
+                                Print("You died! Press ESC to return back to menu".with(theme.bad))
+                            ).handle();
                         }
-                    );
-                    //makes much easier to turn 180 degrees
-                    if snake.last_input.is_opposite_of(&snake.prev_move) == false {
-                        snake.direction = snake.last_input.copy()
-                    };
-                    snake.pos.x = match snake.direction { //snake movement x
-                        Direction::Right => {
-                            if snake.pos.x < (GAME_FIELD_SIZE-1) as u16 {
-                                snake.pos.x + 1
-                            } else {
-                                0
-                            }
-                        },
-                        Direction::Left => {
-                            snake.pos.x
-                                .checked_sub(1)
-                                .unwrap_or((GAME_FIELD_SIZE-1) as u16)
-                        },
-                        _ => snake.pos.x
-                    };
-                    snake.pos.y = match snake.direction { //snake movement y
-                        Direction::Up => {
-                            snake.pos.y
-                                .checked_sub(1)
-                                .unwrap_or((GAME_FIELD_SIZE-1) as u16)
-                        },
-                        Direction::Down => {
-                            if snake.pos.y < (GAME_FIELD_SIZE-1) as u16 {
-                                snake.pos.y + 1
-                            } else {
-                                0
+                        if bot.is_some() { //demo never ends
+                            dead_ticks += 1;
+                            if dead_ticks >= DEMO_RESTART_TICKS {
+                                dead_ticks = 0;
+                                game = start_game(&game_field,&map,snakes,rules,&theme);
+                                if let (Some(recorder),false) = (&mut recorder,demo) {
+                                    recorder.round(&game,&local_names(players,snakes,bot_label.as_deref()));
+                                }
+                                execute!(
+                                    stdout(),
+                                    MoveTo(27,3),
+                                    Clear(ClearType::UntilNewLine)
+                                ).handle();
+                                if demo {
+                                    draw_demo_hint();
+                                }
+                                time = SystemTime::now();
                             }
-                        },
-                        _ => snake.pos.y
+                        }
+                        continue
+                    }
+                    let decision = match bot {
+                        Some(bot) => bot.decide(&game.view(0)),
+                        None => None
                     };
-                    let tail = snake.body.front().unwrap();
-                    queue!(
-                        stdout(),
-                        SetBackgroundColor(COLOR_GRAY),
-                        MoveTo(
-                            tail.x + GAME_FIELD_OFFSET_X,
-                            tail.y + GAME_FIELD_OFFSET_Y
-                        ),
-                        Print(' '),
-                        MoveTo(
-                            snake.pos.x + GAME_FIELD_OFFSET_X,
-                            snake.pos.y + GAME_FIELD_OFFSET_Y
-                        ),
-                        SetForegroundColor(COLOR_GREEN),
-                        Print(
-                            match snake.direction {
-                                Direction::Right => '►',
-                                Direction::Left => '◄',
-                                Direction::Down => '▼',
-                                Direction::Up => '▲'
-                            }
-                        ),
-                        SetForegroundColor(COLOR_RESET)
-                    ).handle();
-                    
+                    let mut decisions = vec![decision];
+                    if players == 1 && !demo {
+                        for (i,rival) in rivals.iter_mut().enumerate() {
+                            let alive = game.snakes[i + 1].alive;
+                            decisions.push(if alive {rival.decide(&game.view(i + 1))} else {None});
+                        }
+                    }
+                    let step = game.step(&decisions);
+                    draw_step(&game,&step,&theme);
+                    if let (Some(recorder),false) = (&mut recorder,demo) {
+                        if !step.walls_added.is_empty() {
+                            recorder.write(&walls_line(&game.walls));
+                        }
+                        recorder.write(&state_line(&game));
+                        if !rounds(players,&rules) && !game.snakes[0].alive {
+                            recorder.write(&over_line(None,&[]));
+                        }
+                    }
+                    if rounds(players,&rules) && game.alive() < snakes.min(2) {
+                        //both heads can crash at the same tick, nobody wins then
+                        let winner = if snakes > 1 {game.snakes.iter().position(|snake| snake.alive)} else {None};
+                        if let Some(player) = winner {
+                            wins[player] += 1;
+                        }
+                        draw_round_over(winner,&wins,&ROUND_HINTS,&theme);
+                        if let Some(recorder) = &mut recorder {
+                            recorder.write(&over_line(winner,&wins));
+                        }
+                    }
                     queue!(
                         stdout(),
-                        MoveTo(
-                            snake.body.back().unwrap().x + GAME_FIELD_OFFSET_X,
-                            snake.body.back().unwrap().y + GAME_FIELD_OFFSET_Y
-                        ),
-                        SetForegroundColor(COLOR_GREEN),
-                        match snake.prev_move { //graphics of snake rotation
-                            Direction::Right => { //formula: invert prev_move and copy cur dir
-                                match snake.direction {
-                                    Direction::Right | Direction::Left => Print('═'),
-                                    Direction::Up => Print('╝'),
-                                    Direction::Down => Print('╗')
-                                }
-                            },
-                            Direction::Left => {
-                                match snake.direction {
-                                    Direction::Right | Direction::Left => Print('═'),
-                                    Direction::Up => Print('╚'),
-                                    Direction::Down => Print('╔')
-                                }
-                            },
-                            Direction::Up => {
-                                match snake.direction {
-                                    Direction::Right => Print('╔'),
-                                    Direction::Left => Print('╗'),
-                                    Direction::Up | Direction::Down => Print('║')
-                                }
-                            },
-                            Direction::Down => {
-                                match snake.direction {
-                                    Direction::Right => Print('╚'),
-                                    Direction::Left => Print('╝'),
-                                    Direction::Up | Direction::Down => Print('║')
-                                }
-                            } 
-                        },
-                        SetBackgroundColor(COLOR_RESET),
-                        SetForegroundColor(COLOR_RESET),
                         MoveTo(GAME_TIME_OFFSET,GLOBAL_OFFSET_Y),
                         Print(time.elapsed().unwrap().as_secs())
                     ).handle();
-                    if snake.pos.x == fruit.pos.x && snake.pos.y == fruit.pos.y {
-                        fruit.respawn(&map,&snake);
-                        score += 1;
-                        queue!(
-                            stdout(),
-                            MoveTo(GAME_SCORE_OFFSET,GLOBAL_OFFSET_Y),
-                            Print(score),
-                            MoveTo(fruit.pos.x+GAME_FIELD_OFFSET_X,fruit.pos.y+GAME_FIELD_OFFSET_Y),
-                            SetBackgroundColor(COLOR_GRAY),
-                            Print(APPLE.red()),
-                        ).handle();
-                    } else {
-                        snake.body.pop_front();
-                    }
                     stdout().flush().handle();
-                    snake.prev_move = snake.direction.copy();
-                    let body = &mut snake.body;
-                    let pos = &snake.pos;
-                    for part in body {
-                        if part.x == pos.x && part.y == pos.y {
-                            snake.alive = false;
-                            continue;
-                        }
-                    }
-                    if map[snake.pos.y as usize][snake.pos.x as usize] == true {
-                        snake.alive = false;
-                        continue;
-                    }
                 }
                 if let InputResult::Abort = input {
                     form = Screen::MainMenu;
-                    score = 0;
+                    dead_ticks = 0;
+                    demo = false;
+                    idle = SystemTime::now();
                     back_to_main_menu(&buttons_ascii);
                 }
             },
@@ -742,23 +1503,20 @@ fn main() {
                     if (parsed_cursor_position.x < GAME_FIELD_SIZE as u16) && 
                         (parsed_cursor_position.y < GAME_FIELD_SIZE as u16)
                     {
-                        if map
+                        if map.walls
                             [parsed_cursor_position.y as usize]
-                            [parsed_cursor_position.x as usize] == true 
+                            [parsed_cursor_position.x as usize]
                         {
-                            COLOR_YELLOW
+                            theme.wall_sign()
+                        } else if is_spawn(&map.spawn,parsed_cursor_position.x,parsed_cursor_position.y) {
+                            theme.block(theme.spawn,SPAWN_SIGN)
+                        } else if map.obstacles.iter().any(|o| o.is_in_point(parsed_cursor_position.x,parsed_cursor_position.y)) {
+                            theme.obstacle_sign()
                         } else {
-                            if (parsed_cursor_position.x >= SNAKE_SPAWN_POS_X) &&
-                                (parsed_cursor_position.x <= SNAKE_SPAWN_POS_X+2) &&
-                                (parsed_cursor_position.y == SNAKE_SPAWN_POS_Y) == true
-                            {
-                                COLOR_RED
-                            } else {
-                                COLOR_GRAY
-                            }
+                            ' '.on(theme.field)
                         }
                     } else {
-                        COLOR_RESET
+                        ' '.on(COLOR_RESET)
                     }
                 );
 
@@ -773,6 +1531,12 @@ fn main() {
                         EDIT_HINT_OFFSET_Y
                     )
                 }
+                if (cursor.y >= EDIT_PALETTE_OFFSET_Y) && (cursor.y < EDIT_PALETTE_OFFSET_Y + 3) {
+                    palette.draw(&theme);
+                }
+                if (cursor.y >= EDIT_MAP_OFFSET_Y) && (cursor.y < EDIT_MAP_OFFSET_Y + 2) {
+                    draw_map_commands(&map_name,map.to_text() != saved_map,map.spawn.players(&map.walls),&theme);
+                }
 
                 let input_result = cursor_input(
                     &mut cursor,
@@ -785,78 +1549,186 @@ fn main() {
                 };
                 let valid = (parsed_cursor_new_position.x < GAME_FIELD_SIZE as u16) &&
                             (parsed_cursor_new_position.y < GAME_FIELD_SIZE as u16); //is cursor on map
-                let snake_rewrite =
-                    (parsed_cursor_new_position.x >= SNAKE_SPAWN_POS_X) &&
-                    (parsed_cursor_new_position.x <= SNAKE_SPAWN_POS_X + 2) && //is cursor at snake spawn pos
-                    (parsed_cursor_new_position.y == SNAKE_SPAWN_POS_Y);
-                if let InputResult::Draw = input_result {
-                    if valid == true && snake_rewrite == false {
-                        if (parsed_cursor_new_position.x != parsed_cursor_position.x) ||
-                           (parsed_cursor_new_position.y != parsed_cursor_position.y)
-                        {
-                            map
-                                [parsed_cursor_new_position.y as usize]
-                                [parsed_cursor_new_position.x as usize] = 
-                                    !map
-                                        [parsed_cursor_new_position.y as usize]
-                                        [parsed_cursor_new_position.x as usize];
-                            //set map cell to opposite of self
+                let moved = parsed_cursor_new_position != parsed_cursor_position;
+                let mut changed = false; //map needs redrawing
+                match input_result {
+                    //spawn is dropped on the second click or when mouse button is up somewhere else
+                    InputResult::Click | InputResult::Release if valid && grabbed.is_some_and(|from|
+                        matches!(input_result,InputResult::Click) || from != parsed_cursor_new_position
+                    ) => {
+                        let spawn = Spawn {pos:parsed_cursor_new_position,direction:map.spawn.direction.copy()};
+                        if spawn.pos != map.spawn.pos && spawn.fits(&map.walls,1) {
+                            history.record(&map);
+                            map.spawn = spawn;
                         }
-                        
-                    }
+                        grabbed = None;
+                        changed = true;
+                    },
+                    InputResult::Click if valid && palette.anchor.is_none() &&
+                        Snake::player(&map.spawn,0).is_in_point(parsed_cursor_new_position.x,parsed_cursor_new_position.y) => {
+                        grabbed = Some(parsed_cursor_new_position);
+                        changed = true;
+                    },
+                    InputResult::Key('r' | 'R') => { //first direction that doesn't face a wall
+                        let turned = successors(Some(map.spawn.turned()),|spawn| Some(spawn.turned()))
+                            .take(3)
+                            .find(|spawn| spawn.fits(&map.walls,1));
+                        if let Some(spawn) = turned {
+                            history.record(&map);
+                            map.spawn = spawn;
+                            changed = true;
+                        }
+                    },
+                    InputResult::Draw if valid && moved && grabbed.is_none() && !palette.is_shape() && palette.tool != Tool::Fill => {
+                        let from = if (parsed_cursor_position.x < GAME_FIELD_SIZE as u16) &&
+                            (parsed_cursor_position.y < GAME_FIELD_SIZE as u16)
+                        {parsed_cursor_position} else {parsed_cursor_new_position}; //fast drag jumps over cells
+                        let cells = palette.cells(&map.walls,from,parsed_cursor_new_position);
+                        let before = map.clone();
+                        if palette.apply(&mut map,&cells) && !dragging {
+                            history.record(&before);
+                            dragging = true;
+                        }
+                        changed = true;
+                    },
+                    InputResult::Click if valid => {
+                        if let Some(anchor) = palette.anchor.take() { //second point of a shape
+                            let cells = palette.cells(&map.walls,anchor,parsed_cursor_new_position);
+                            let before = map.clone();
+                            if palette.apply(&mut map,&cells) {history.record(&before)}
+                        } else if palette.is_shape() {
+                            palette.anchor = Some(parsed_cursor_new_position);
+                        } else { //mouse button is down, drag may follow and goes into the same step
+                            let cells = palette.cells(&map.walls,parsed_cursor_new_position,parsed_cursor_new_position);
+                            let before = map.clone();
+                            dragging = palette.apply(&mut map,&cells);
+                            if dragging {history.record(&before)}
+                        }
+                        changed = true;
+                    },
+                    //shape dragged with mouse is done when button is up, a plain click waits for the second one
+                    InputResult::Release if valid && palette.anchor.is_some_and(|anchor| anchor != parsed_cursor_new_position) => {
+                        let anchor = palette.anchor.take().unwrap_or(parsed_cursor_new_position);
+                        let cells = palette.cells(&map.walls,anchor,parsed_cursor_new_position);
+                        let before = map.clone();
+                        if palette.apply(&mut map,&cells) {history.record(&before)}
+                        changed = true;
+                    },
+                    InputResult::Click if cursor.hover == EDIT_GENERATE => {
+                        if let Some(generated) = ask_generated_map(&map.spawn,&theme) { //can be undone like any other change
+                            history.record(&map);
+                            map = generated;
+                            palette.anchor = None;
+                            grabbed = None;
+                            changed = true;
+                        }
+                    },
+                    InputResult::Click if EDIT_MAP_BUTTONS.iter().any(|button| button.return_code == cursor.hover) => {
+                        let opened = map_command(cursor.hover,&mut map,&mut map_name,&mut saved_map,&theme);
+                        if opened { //another map, nothing to undo there
+                            history = History::new();
+                            palette.anchor = None;
+                            grabbed = None;
+                            changed = true;
+                        }
+                    },
+                    InputResult::Save => saved_map = save_map(&map,&map_name),
+                    InputResult::Click if cursor.hover == EDIT_RESET => {
+                        let mut walls = [[false;GAME_FIELD_SIZE];GAME_FIELD_SIZE];
+                        reset_map(&mut walls);
+                        if walls != map.walls {
+                            history.record(&map);
+                            map.walls = walls;
+                        }
+                        palette.anchor = None;
+                        changed = true;
+                    },
+                    InputResult::Click if palette.click(cursor.x,cursor.y) => {
+                        palette.draw(&theme);
+                        changed = true; //anchor is dropped
+                    },
+                    InputResult::Key(key) if palette.key(key) => {
+                        palette.draw(&theme);
+                        changed = true;
+                    },
+                    InputResult::Abort if palette.anchor.is_some() || grabbed.is_some() => { //ESC drops unfinished shape first
+                        palette.anchor = None;
+                        grabbed = None;
+                        changed = true;
+                    },
+                    InputResult::Abort => {
+                        saved_map = save_map(&map,&map_name);
+                        form = Screen::MainMenu;
+                        back_to_main_menu(&buttons_ascii);
+                    },
+                    _ => ()
                 }
-                else if let InputResult::Click = input_result {
-                    if valid == true && snake_rewrite == false {
-                        map
-                            [parsed_cursor_new_position.y as usize]
-                            [parsed_cursor_new_position.x as usize] = 
-                                !map
-                                    [parsed_cursor_new_position.y as usize]
-                                    [parsed_cursor_new_position.x as usize];
-                    } else if cursor.hover == 1 {
-                        map = [[false;GAME_FIELD_SIZE];GAME_FIELD_SIZE];
-                        reset_map(&mut map);
-                        draw_map(&map,GLOBAL_OFFSET_X,GLOBAL_OFFSET_Y);
-                        queue!(
-                            stdout(),
-                            MoveTo(
-                                GLOBAL_OFFSET_X + SNAKE_SPAWN_POS_X,
-                                GLOBAL_OFFSET_Y + SNAKE_SPAWN_POS_Y,
-                            ),
-                            Print("   ".on_red())
-                        ).handle();
-                        stdout().flush().handle();
-                    }
+                if !matches!(input_result,InputResult::Draw | InputResult::Click) {
+                    dragging = false;
                 }
-                else if let InputResult::Abort = input_result {
-                    form = Screen::MainMenu;
-                    back_to_main_menu(&buttons_ascii);
+                let undo = matches!(input_result,InputResult::Undo) ||
+                    (matches!(input_result,InputResult::Click) && cursor.hover == EDIT_UNDO);
+                let redo = matches!(input_result,InputResult::Redo) ||
+                    (matches!(input_result,InputResult::Click) && cursor.hover == EDIT_REDO);
+                let restored = if undo {
+                    history.undo(&map)
+                } else if redo {
+                    history.redo(&map)
+                } else {None};
+                if let Some(previous) = restored {
+                    map = previous;
+                    palette.anchor = None;
+                    grabbed = None;
+                    changed = true;
                 }
-                cursor.hover = get_hover(cursor.x,cursor.y,&EDIT_RESET_BUTTON);
-                draw(
-                    &cursor,
-                    if cursor.hover == NONE {
-                        COLOR_WHITE
-                    } else {
-                        COLOR_BLUE
+                if let Screen::Edit = form {
+                    let unsaved = map.to_text() != saved_map;
+                    if changed && unsaved {
+                        autosave(&map,&map_name);
+                    } else if changed {
+                        forget_recovery(&map_name); //undone back to the saved one
                     }
-                );
+                    draw_map_commands(&map_name,unsaved,map.spawn.players(&map.walls),&theme);
+                    if changed || ((palette.anchor.is_some() || grabbed.is_some()) && moved) {
+                        draw_map(&map.walls,GLOBAL_OFFSET_X,GLOBAL_OFFSET_Y,&theme);
+                        draw_obstacles(&map.obstacles,GLOBAL_OFFSET_X,GLOBAL_OFFSET_Y,&theme);
+                        draw_spawns(&map.spawn,GLOBAL_OFFSET_X,GLOBAL_OFFSET_Y,&theme);
+                    }
+                    if let (Some(anchor),true) = (palette.anchor,valid) { //preview of the shape
+                        for cell in palette.cells(&map.walls,anchor,parsed_cursor_new_position) {
+                            queue!(
+                                stdout(),
+                                MoveTo(cell.x + GLOBAL_OFFSET_X,cell.y + GLOBAL_OFFSET_Y),
+                                Print(theme.block(theme.preview,PREVIEW_SIGN))
+                            ).handle();
+                        }
+                    }
+                    let spawn = Spawn {pos:parsed_cursor_new_position,direction:map.spawn.direction.copy()};
+                    if grabbed.is_some() && valid && spawn.fits(&map.walls,1) { //where the spawn would go
+                        let snake = Snake::player(&spawn,0);
+                        for part in snake.body.iter().chain([&snake.pos]) {
+                            queue!(
+                                stdout(),
+                                MoveTo(part.x + GLOBAL_OFFSET_X,part.y + GLOBAL_OFFSET_Y),
+                                Print(theme.block(theme.preview,PREVIEW_SIGN))
+                            ).handle();
+                        }
+                    }
+                }
+                cursor.hover = if palette.is_over(cursor.x,cursor.y) {EDIT_PALETTE} else {NONE};
+                for button in EDIT_BUTTONS.iter().chain(&EDIT_MAP_BUTTONS) {
+                    let code = get_hover(cursor.x,cursor.y,button);
+                    if code != NONE {
+                        cursor.hover = code;
+                        break
+                    }
+                }
+                draw(&cursor,theme.cursor_sign(cursor.hover != NONE));
                 stdout().flush().handle();
             }
         }
     }
-    // saving map in map.txt
-    let mut file = OpenOptions::new()
-        .create(true)
-        .write(true)
-        .open(FILE_MAP)
-        .handle_open(FILE_MAP);
-    for line in &map {
-        for i in line {
-            file.write(if i == &false {b"0"} else {b"1"}).handle_write();
-        }
-        file.write(b"\n").handle_write();
-    }
-    file.flush().handle();
+    // saving map in maps dir
+    save_map(&map,&map_name);
     free_window(term_old_w,term_old_h);
 }
\ No newline at end of file
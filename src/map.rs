@@ -0,0 +1,412 @@
+use std::{
+    io,
+    fs
+};
+
+use crate::{
+    Pos,
+    Direction,
+    DirectionFunctionality,
+    GAME_FIELD_SIZE,
+    MAX_PLAYERS,
+    MAPS_DIR,
+    FILE_MAP,
+    DEFAULT_MAP,
+    SNAKE_SPAWN_POS_X,
+    SNAKE_SPAWN_POS_Y,
+    next_pos,
+    general::error_handling::*,
+    obstacles::{Obstacle,ObstacleFunctionality},
+    external::{direction_name,parse_direction}
+};
+
+pub type Walls = [[bool;GAME_FIELD_SIZE];GAME_FIELD_SIZE];
+
+const MAP_EXTENSION:&str = ".txt";
+const RECOVERY_EXTENSION:&str = ".recovery"; //unsaved editor changes
+const TEMP_EXTENSION:&str = ".tmp";
+const MAP_NAME_LENGTH:usize = 16;
+
+//quarter turns clockwise from the first spawn: second player is mirrored to the opposite corner,
+//the other two take the remaining corners
+const PLAYER_TURNS:[usize;MAX_PLAYERS] = [0,2,1,3];
+
+#[derive(Clone)]
+pub struct Map {
+    pub walls:Walls,
+    pub obstacles:Vec<Obstacle>,
+    pub spawn:Spawn,
+    pub fruits:Vec<Pos> //where fruit comes in this order, random places after that
+}
+
+//where the first snake's head starts and where it looks, everybody else gets it turned to their corner
+pub struct Spawn {
+    pub pos:Pos,
+    pub direction:Direction
+}
+
+impl Clone for Spawn {
+    fn clone(&self) -> Spawn {
+        Spawn {pos:self.pos,direction:self.direction.copy()}
+    }
+}
+
+impl Spawn {
+    pub fn new() -> Spawn {
+        Spawn {
+            pos:Pos {x:SNAKE_SPAWN_POS_X + 2,y:SNAKE_SPAWN_POS_Y},
+            direction:Direction::Right
+        }
+    }
+
+    //format: spawn <x> <y> <up|right|left|down>
+    fn parse(line:&str) -> Option<Spawn> {
+        let words = line.split_whitespace().collect::<Vec<&str>>();
+        let ["spawn",x,y,direction] = words[..] else {return None};
+        let pos = Pos {x:x.parse().ok()?,y:y.parse().ok()?};
+        if pos.x as usize >= GAME_FIELD_SIZE || pos.y as usize >= GAME_FIELD_SIZE {return None}
+        Some(Spawn {pos,direction:parse_direction(direction)?})
+    }
+
+    fn to_line(&self) -> String {
+        format!("spawn {} {} {}",self.pos.x,self.pos.y,direction_name(&self.direction))
+    }
+
+    //turned around the center of the field
+    fn rotated(&self,turns:usize) -> Spawn {
+        let last = (GAME_FIELD_SIZE - 1) as u16;
+        let mut spawn = self.clone();
+        for _ in 0..turns % 4 {
+            spawn = Spawn {
+                pos:Pos {x:last - spawn.pos.y,y:spawn.pos.x},
+                direction:match spawn.direction {
+                    Direction::Up => Direction::Right,
+                    Direction::Right => Direction::Down,
+                    Direction::Down => Direction::Left,
+                    Direction::Left => Direction::Up
+                }
+            };
+        }
+        spawn
+    }
+
+    pub fn player(&self,index:usize) -> Spawn {
+        self.rotated(PLAYER_TURNS[index % MAX_PLAYERS])
+    }
+
+    //next direction clockwise
+    pub fn turned(&self) -> Spawn {
+        let mut spawn = self.rotated(1);
+        spawn.pos = self.pos;
+        spawn
+    }
+
+    //head, neck and tail, unless the body would stick out of the field
+    fn cells(&self) -> Option<[Pos;3]> {
+        let (x,y) = (self.pos.x as i32,self.pos.y as i32);
+        let (dx,dy) = match self.direction {
+            Direction::Up => (0,1), //body goes the other way
+            Direction::Right => (-1,0),
+            Direction::Down => (0,-1),
+            Direction::Left => (1,0)
+        };
+        let field = 0..GAME_FIELD_SIZE as i32;
+        if [x,y,x + 2*dx,y + 2*dy].iter().any(|coord| !field.contains(coord)) {return None}
+        let cell = |i:i32| Pos {x:(x + i*dx) as u16,y:(y + i*dy) as u16};
+        Some([cell(0),cell(1),cell(2)])
+    }
+
+    //cells every player's snake takes at the start and the ones right in front of them
+    pub fn area(&self) -> Vec<Pos> {
+        let mut area = vec![];
+        for player in 0..MAX_PLAYERS {
+            let spawn = self.player(player);
+            area.extend(spawn.cells().into_iter().flatten());
+            area.push(next_pos(&spawn.pos,&spawn.direction));
+        }
+        area
+    }
+
+    //first `players` snakes are on free cells, don't overlap each other and don't start facing a wall or a snake
+    pub fn fits(&self,walls:&Walls,players:usize) -> bool {
+        let mut taken = vec![];
+        let mut ahead = vec![];
+        for player in 0..players {
+            let spawn = self.player(player);
+            let Some(cells) = spawn.cells() else {return false};
+            if cells.iter().any(|cell| walls[cell.y as usize][cell.x as usize] || taken.contains(cell)) {return false}
+            taken.extend(cells);
+            ahead.push(next_pos(&spawn.pos,&spawn.direction));
+        }
+        ahead.iter().all(|cell| !walls[cell.y as usize][cell.x as usize] && !taken.contains(cell))
+    }
+
+    //how many players can start here, walls on the other corners only matter for bigger games
+    pub fn players(&self,walls:&Walls) -> usize {
+        (1..=MAX_PLAYERS).rev().find(|players| self.fits(walls,*players)).unwrap_or(0)
+    }
+}
+
+pub trait MapFunctionality {
+    fn new() -> Self;
+    fn parse(s:&str) -> Self;
+    fn load(filename:&str) -> Self;
+    fn to_text(&self) -> String;
+    fn save(&self,filename:&str);
+}
+
+impl MapFunctionality for Map {
+    fn new() -> Map {
+        let mut map = Map {
+            walls:[[false;GAME_FIELD_SIZE];GAME_FIELD_SIZE],
+            obstacles:vec![],
+            spawn:Spawn::new(),
+            fruits:vec![]
+        };
+        reset_map(&mut map.walls);
+        map
+    }
+
+    //first GAME_FIELD_SIZE lines are walls, everything after them is spawn, fruit and obstacles
+    fn parse(s:&str) -> Map {
+        let mut map = Map {
+            walls:[[false;GAME_FIELD_SIZE];GAME_FIELD_SIZE],
+            obstacles:vec![],
+            spawn:Spawn::new(),
+            fruits:vec![]
+        };
+        for (y,line) in s.lines().enumerate() {
+            if y < GAME_FIELD_SIZE {
+                for (x,ch) in line.chars().take(GAME_FIELD_SIZE).enumerate() {
+                    map.walls[y][x] = ch == '1';
+                }
+            } else if let Some(spawn) = Spawn::parse(line) {
+                map.spawn = spawn;
+            } else if let Some(fruit) = parse_fruit(line) {
+                map.fruits.push(fruit);
+            } else if let Some(obstacle) = Obstacle::parse(line,&map.walls) {
+                map.obstacles.push(obstacle);
+            }
+        }
+        map
+    }
+
+    fn load(filename:&str) -> Map {
+        match fs::read_to_string(filename) { //if life gives you files - read them
+            Ok(s) => Map::parse(&s),
+            Err(_) => Map::new()
+        }
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = String::new();
+        for line in &self.walls {
+            text.extend(line.iter().map(|wall| if *wall {'1'} else {'0'}));
+            text.push('\n');
+        }
+        text += &self.spawn.to_line();
+        text.push('\n');
+        for fruit in &self.fruits {
+            text += &format!("fruit {} {}\n",fruit.x,fruit.y);
+        }
+        for obstacle in &self.obstacles {
+            text += &obstacle.to_line();
+            text.push('\n');
+        }
+        text
+    }
+
+    fn save(&self,filename:&str) {
+        write_atomically(filename,&self.to_text()).handle_write();
+    }
+}
+
+//format: fruit <x> <y>
+fn parse_fruit(line:&str) -> Option<Pos> {
+    let words = line.split_whitespace().collect::<Vec<&str>>();
+    let ["fruit",x,y] = words[..] else {return None};
+    let pos = Pos {x:x.parse().ok()?,y:y.parse().ok()?};
+    if pos.x as usize >= GAME_FIELD_SIZE || pos.y as usize >= GAME_FIELD_SIZE {return None}
+    Some(pos)
+}
+
+//new file is written next to the old one and then takes its place, so it's never half written
+pub fn write_atomically(filename:&str,text:&str) -> io::Result<()> {
+    let temp = format!("{}{}",filename,TEMP_EXTENSION);
+    fs::write(&temp,text)?;
+    fs::rename(&temp,filename)
+}
+
+pub fn reset_map(walls:&mut Walls) {
+    wall_ring(walls,0);
+}
+
+//turns ring number `ring` (0 is the border) into walls and tells which cells weren't walls before
+pub fn wall_ring(walls:&mut Walls,ring:usize) -> Vec<Pos> {
+    let far = GAME_FIELD_SIZE - 1 - ring;
+    let mut added = vec![];
+    for (y,line) in walls.iter_mut().enumerate().take(far + 1).skip(ring) {
+        for (x,wall) in line.iter_mut().enumerate().take(far + 1).skip(ring) {
+            let on_ring = y == ring || y == far || x == ring || x == far;
+            if on_ring && !*wall {
+                *wall = true;
+                added.push(Pos {x:x as u16,y:y as u16});
+            }
+        }
+    }
+    added
+}
+
+//every map is a file in MAPS_DIR named after it
+pub fn map_path(name:&str) -> String {
+    format!("{}/{}{}",MAPS_DIR,name,MAP_EXTENSION)
+}
+
+fn recovery_path(name:&str) -> String {
+    format!("{}/{}{}",MAPS_DIR,name,RECOVERY_EXTENSION)
+}
+
+//map and the text of its file as it was saved, if the game was closed with unsaved changes they're back
+pub fn open_map(name:&str) -> (Map,String) {
+    let saved = fs::read_to_string(map_path(name)).unwrap_or_default();
+    let map = match fs::read_to_string(recovery_path(name)) {
+        Ok(recovered) => Map::parse(&recovered),
+        Err(_) => Map::load(&map_path(name))
+    };
+    (map,saved)
+}
+
+//writes the map and forgets its unsaved changes, returns the saved text
+pub fn save_map(map:&Map,name:&str) -> String {
+    map.save(&map_path(name));
+    forget_recovery(name);
+    map.to_text()
+}
+
+//keeps unsaved changes in case the game is closed without saving them
+pub fn autosave(map:&Map,name:&str) {
+    write_atomically(&recovery_path(name),&map.to_text()).ok(); //the edits themselves are still in memory, only the crash copy is missing
+}
+
+//another map is opened, unsaved changes of this one wait for it in the recovery file
+pub fn keep_unsaved(map:&Map,name:&str,saved:&str) {
+    if map.to_text() != saved {
+        autosave(map,name);
+    }
+}
+
+pub fn forget_recovery(name:&str) {
+    fs::remove_file(recovery_path(name)).ok();
+}
+
+pub fn rename_map(from:&str,to:&str) -> io::Result<()> {
+    fs::rename(recovery_path(from),recovery_path(to)).ok(); //there may be no unsaved changes
+    fs::rename(map_path(from),map_path(to))
+}
+
+pub fn delete_map(name:&str) {
+    forget_recovery(name);
+    fs::remove_file(map_path(name)).ok();
+}
+
+pub fn map_exists(name:&str) -> bool {
+    fs::metadata(map_path(name)).is_ok()
+}
+
+//sorted names of all maps in MAPS_DIR
+pub fn list_maps() -> Vec<String> {
+    list_txt_in(MAPS_DIR)
+}
+
+//names of every .txt file there, themes are kept the same way as maps
+pub fn list_txt_in(dir:&str) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {return vec![]};
+    let mut names = entries
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter_map(|file| Some(file.strip_suffix(MAP_EXTENSION)?.to_string()))
+        .collect::<Vec<String>>();
+    names.sort();
+    names
+}
+
+//names become file names, so only letters, digits, - and _ are allowed (spaces turn into _)
+pub fn clean_map_name(name:&str) -> Option<String> {
+    let name = name.trim().replace(' ',"_");
+    let fits = !name.is_empty() && name.chars().count() <= MAP_NAME_LENGTH &&
+        name.chars().all(|ch| ch.is_alphanumeric() || ch == '-' || ch == '_');
+    if fits {Some(name)} else {None}
+}
+
+//map to start with: the default one, or any if it's gone; old single map file becomes the default map
+pub fn starting_map() -> String {
+    fs::create_dir_all(MAPS_DIR).ok();
+    let maps = list_maps();
+    if maps.is_empty() {
+        Map::load(FILE_MAP).save(&map_path(DEFAULT_MAP));
+        return DEFAULT_MAP.to_string()
+    }
+    if maps.iter().any(|name| name == DEFAULT_MAP) {DEFAULT_MAP.to_string()} else {maps[0].clone()}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn(x:u16,y:u16,direction:Direction) -> Spawn {
+        Spawn {pos:Pos {x,y},direction}
+    }
+
+    fn same(a:&Spawn,b:&Spawn) -> bool {
+        a.pos == b.pos && direction_name(&a.direction) == direction_name(&b.direction)
+    }
+
+    #[test]
+    fn rotates_around_the_center() {
+        let start = spawn(4,2,Direction::Right);
+        assert!(same(&start.rotated(1),&spawn(17,4,Direction::Down)));
+        assert!(same(&start.rotated(2),&spawn(15,17,Direction::Left)));
+        assert!(same(&start.rotated(3),&spawn(2,15,Direction::Up)));
+        assert!(same(&start.rotated(4),&start) && same(&start.rotated(6),&start.rotated(2)));
+        //second player gets the opposite corner
+        assert!(same(&start.player(1),&start.rotated(2)) && same(&start.player(0),&start));
+        assert!(same(&start.turned(),&spawn(4,2,Direction::Down)));
+    }
+
+    #[test]
+    fn fits_only_who_plays() {
+        let mut walls = Map::new().walls;
+        let start = Spawn::new();
+        assert_eq!(start.players(&walls),MAX_PLAYERS);
+        let second = start.player(1).pos;
+        walls[second.y as usize][second.x as usize] = true;
+        assert!(start.fits(&walls,1) && !start.fits(&walls,2));
+        assert_eq!(start.players(&walls),1);
+        //facing a wall doesn't fit even for one
+        let ahead = next_pos(&start.pos,&start.direction);
+        walls[ahead.y as usize][ahead.x as usize] = true;
+        assert_eq!(start.players(&walls),0);
+        //body out of the field
+        assert!(!spawn(1,5,Direction::Right).fits(&Map::new().walls,1));
+        assert!(spawn(1,5,Direction::Up).fits(&Map::new().walls,1));
+    }
+
+    #[test]
+    fn text_goes_both_ways() {
+        let mut map = Map::new();
+        map.walls[5][7] = true;
+        map.spawn = spawn(6,12,Direction::Up);
+        map.fruits = vec![Pos {x:3,y:4},Pos {x:10,y:2}];
+        map.obstacles.push(Obstacle::parse("bounce 4 4 1 -1 3",&map.walls).unwrap());
+        map.obstacles.push(Obstacle::parse("bar 10 10 2",&map.walls).unwrap());
+        let text = map.to_text();
+        let parsed = Map::parse(&text);
+        assert!(parsed.walls == map.walls && parsed.fruits == map.fruits && parsed.obstacles == map.obstacles);
+        assert!(same(&parsed.spawn,&map.spawn));
+        assert_eq!(parsed.to_text(),text);
+        //broken lines are skipped, whatever is missing stays as in a new map
+        let text = "11\n".to_string() + &"0\n".repeat(GAME_FIELD_SIZE - 1) + "spawn 40 1 up\nfruit 1\nbar 5 5 x\n";
+        let parsed = Map::parse(&text);
+        assert!(parsed.walls[0][0] && parsed.walls[0][1] && !parsed.walls[0][2] && !parsed.walls[5][0]);
+        assert!(same(&parsed.spawn,&Spawn::new()) && parsed.fruits.is_empty() && parsed.obstacles.is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    io::{Write,stdout},
+    time::{SystemTime,Duration}
+};
+
+use crossterm::{
+    execute, queue,
+    terminal::{Clear,ClearType},
+    cursor::MoveTo,
+    style::{Print,Stylize}
+};
+
+use crate::{
+    GAME_TICK,
+    GAME_TIME_OFFSET,
+    GAME_FIELD_OFFSET_Y,
+    GAME_INFO_X,
+    GLOBAL_OFFSET_Y,
+    start_game,
+    draw_step,
+    draw_box,
+    wait_for_start,
+    confirm,
+    game_seconds,
+    general::{error_handling::*,input::*,list::*},
+    engine::{Game,GameFunctionality,Rules,Corpses},
+    map::Map,
+    scores::{Scores,SCORES_KEPT},
+    theme::Theme
+};
+
+const SURVIVAL_GROW:u32 = 20; //ticks between snake growing by itself, 5 seconds
+const NAME_WIDTH:usize = 16;
+const ABOUT_WIDTH:usize = 36;
+const TABLE_X:u16 = LIST_X + (NAME_WIDTH + ABOUT_WIDTH) as u16 + 4;
+const HINTS:&str = "Arrows, Mouse - choose mode\nENTER, LMB - play it\nESC - back";
+const INFO_OFFSET_Y:u16 = GAME_FIELD_OFFSET_Y + 2;
+const CLASSIC:Rules = Rules {corpses:Corpses::Stay,shrink:0,fruits:0,grow:0,zen:false};
+
+//ways to play alone besides the endless one
+struct Mode {
+    key:&'static str, //how it's called in the scores file
+    name:&'static str,
+    about:&'static str,
+    limit:u32, //seconds to play, 0 = until the snake dies
+    rules:Rules,
+    seconds:bool //score is how long the snake lived and not how much it ate
+}
+
+const MODES:[Mode;4] = [
+    Mode {key:"time60",name:"Time attack 60",about:"most apples in 60 seconds",limit:60,rules:CLASSIC,seconds:false},
+    Mode {key:"time120",name:"Time attack 120",about:"most apples in 120 seconds",limit:120,rules:CLASSIC,seconds:false},
+    Mode {
+        key:"survival",
+        name:"Survival",
+        about:"live long, snake grows every 5 s",
+        limit:0,
+        rules:Rules {grow:SURVIVAL_GROW,..CLASSIC},
+        seconds:true
+    },
+    Mode {key:"zen",name:"Zen",about:"goes through itself, ESC ends it",limit:0,rules:Rules {zen:true,..CLASSIC},seconds:false}
+];
+
+impl Mode {
+    fn score(&self,game:&Game) -> u32 {
+        if self.seconds {game_seconds(game)} else {game.snakes[0].score as u32}
+    }
+
+    fn score_text(&self,score:u32) -> String {
+        if self.seconds {format!("{} s",score)} else {score.to_string()}
+    }
+}
+
+fn draw_modes(scores:&Scores,selected:usize,map_name:&str,theme:&Theme) {
+    queue!(stdout(),MoveTo(LIST_X,GLOBAL_OFFSET_Y),Print(format!("Modes   map: {}",map_name))).handle();
+    for (row,mode) in MODES.iter().enumerate() {
+        let line = format!(" {:<name$} {:<about$}",mode.name,mode.about,name = NAME_WIDTH,about = ABOUT_WIDTH);
+        queue!(
+            stdout(),
+            MoveTo(LIST_X,LIST_Y + row as u16),
+            Print(if row == selected {theme.highlight(line)} else {line.reset()})
+        ).handle();
+    }
+    let mode = &MODES[selected];
+    let table = scores.table(mode.key);
+    queue!(stdout(),MoveTo(TABLE_X,LIST_Y),Clear(ClearType::UntilNewLine),Print("High scores:")).handle();
+    for place in 0..SCORES_KEPT {
+        let line = match table.get(place) {
+            Some((score,map)) => format!("{}. {:<6} {}",place + 1,mode.score_text(*score),map),
+            None => format!("{}. -",place + 1)
+        };
+        queue!(stdout(),MoveTo(TABLE_X,LIST_Y + 1 + place as u16),Clear(ClearType::UntilNewLine),Print(line)).handle();
+    }
+}
+
+//mode selector, the chosen mode is played on the current map until ESC
+pub fn run(game_field:&str,map:&Map,map_name:&str,theme:&Theme) {
+    let mut scores = Scores::load();
+    let mut selected = 0;
+    loop {
+        execute!(stdout(),Clear(ClearType::All)).handle();
+        let mut list = List::new(MODES.len(),selected);
+        list.draw_hints(HINTS,SCORES_KEPT + 1);
+        loop {
+            draw_modes(&scores,selected,map_name,theme);
+            stdout().flush().handle();
+            let input = list.input();
+            selected = list.selected;
+            match input {
+                InputResult::Click => break,
+                InputResult::Abort => return,
+                _ => ()
+            }
+        }
+        let mode = &MODES[selected];
+        while let Some((title,score)) = play(game_field,map,mode,&scores,theme) {
+            let best = score > 0 && scores.add(mode.key,score,map_name,false);
+            let result = format!("score {}{}",mode.score_text(score),if best {" best!"} else {""});
+            draw_box(title.to_string().with(theme.bad),&[result,"ENTER - again".to_string(),"ESC - modes".to_string()],theme);
+            if !confirm() {break}
+        }
+    }
+}
+
+fn draw_info(mode:&Mode,scores:&Scores) {
+    let best = scores.table(mode.key).first().map_or("-".to_string(),|(score,_)| mode.score_text(*score));
+    let lines = [mode.name.to_string(),mode.about.to_string(),format!("Best: {}",best)];
+    for (row,line) in (0u16..).zip(lines) {
+        queue!(stdout(),MoveTo(GAME_INFO_X,INFO_OFFSET_Y + row),Print(line)).handle();
+    }
+}
+
+//time attack counts down in the Time slot
+fn draw_time(mode:&Mode,game:&Game) {
+    let seconds = game_seconds(game);
+    let time = if mode.limit > 0 {mode.limit.saturating_sub(seconds)} else {seconds};
+    queue!(stdout(),MoveTo(GAME_TIME_OFFSET,GLOBAL_OFFSET_Y),Print(format!("{:<4}",time))).handle();
+}
+
+//title for the box and the score, None if the game was left before it was over
+fn play(game_field:&str,map:&Map,mode:&Mode,scores:&Scores,theme:&Theme) -> Option<(&'static str,u32)> {
+    let mut game = start_game(game_field,map,1,mode.rules,theme);
+    draw_info(mode,scores);
+    draw_time(mode,&game);
+    stdout().flush().handle();
+    wait_for_start();
+    execute!(stdout(),MoveTo(GAME_INFO_X,3),Clear(ClearType::UntilNewLine)).handle();
+    let mut tick = SystemTime::now();
+    loop {
+        let elapsed = tick.elapsed().unwrap().as_millis();
+        let wait = Duration::from_millis(GAME_TICK.saturating_sub(elapsed as u64));
+        if let InputResult::Abort = game_input(&mut game.snakes[..1],wait) {
+            //zen has no time limit, so leaving it is how it's finished and it still counts
+            return if mode.rules.zen {Some(("ZEN OVER",mode.score(&game)))} else {None}
+        }
+        if elapsed < GAME_TICK as u128 {continue}
+        tick = SystemTime::now();
+        let step = game.step(&[None]);
+        draw_step(&game,&step,theme);
+        draw_time(mode,&game);
+        stdout().flush().handle();
+        if !game.snakes[0].alive {return Some(("GAME OVER!",mode.score(&game)))}
+        if mode.limit > 0 && game_seconds(&game) >= mode.limit {return Some(("TIME'S UP!",mode.score(&game)))}
+    }
+}
@@ -0,0 +1,650 @@
+use std::{
+    io::{self,Read,Write,BufRead,BufReader,stdout},
+    net::{TcpListener,TcpStream},
+    sync::mpsc::{channel,Sender,Receiver,TryRecvError},
+    thread,
+    time::{SystemTime,Duration},
+    collections::VecDeque
+};
+
+use crossterm::{
+    execute, queue,
+    terminal::{Clear,ClearType},
+    cursor::MoveTo,
+    style::{Print,Stylize}
+};
+
+use crate::{
+    Pos,
+    Direction,
+    DirectionFunctionality,
+    Snake,
+    GAME_FIELD_SIZE,
+    GAME_FIELD_OFFSET_X,
+    GAME_FIELD_OFFSET_Y,
+    GAME_INFO_X,
+    GAME_TICK,
+    GAME_TIME_OFFSET,
+    GLOBAL_OFFSET_Y,
+    MAX_PLAYERS,
+    player_color,
+    start_game,
+    draw_step,
+    draw_map,
+    draw_state,
+    draw_round_over,
+    wait_for_start,
+    general::{error_handling::*,graphics::*,input::*},
+    engine::{Game,GameFunctionality,Corpses,Rules},
+    map::{Map,Walls},
+    obstacles::ObstacleFunctionality,
+    external::{direction_name,parse_direction},
+    replay::Recorder,
+    theme::Theme
+};
+
+pub const PORT:u16 = 7777; //default for --port
+const POLL:u64 = 50; //ms between checks for new players and messages when nothing is going on
+const HELLO_TIMEOUT:u64 = 1000; //ms new connection has to say who it is
+const WRITE_TIMEOUT:u64 = 100; //ms client can keep host waiting before it's dropped
+pub const ANY_ADDRESS:&str = "0.0.0.0"; //default for --bind, every network interface
+const NAME_LENGTH:usize = 12;
+const MAX_LINE:usize = 1 << 16; //bytes, the longest state fits many times, whoever sends more is dropped
+const HOST_HINTS:[&str;2] = ["ENTER - again","ESC - quit"];
+const CLIENT_HINTS:[&str;2] = ["wait for host","ESC - quit"];
+const SPECTATOR_HINTS:[&str;2] = ["","ESC - quit"];
+const NAMES_OFFSET_Y:u16 = GAME_FIELD_OFFSET_Y + 3;
+
+//everything needed to draw one tick, the only thing clients know about the game
+pub struct State {
+    pub tick:u32,
+    pub fruits:Vec<Pos>, //the real one goes first, then leftovers of dead snakes
+    pub obstacles:Vec<Pos>,
+    pub snakes:Vec<Snake>
+}
+
+//like read_line, but gives up on a line that doesn't end in time instead of filling the memory with it
+fn capped_line(reader:&mut impl BufRead,line:&mut String) -> io::Result<usize> {
+    let read = reader.take(MAX_LINE as u64).read_line(line)?;
+    if read == MAX_LINE && !line.ends_with('\n') {
+        return Err(io::Error::new(io::ErrorKind::InvalidData,"line too long"))
+    }
+    Ok(read)
+}
+
+fn forward(mut reader:BufReader<TcpStream>,sender:Sender<String>) {
+    loop {
+        let mut line = String::new();
+        if !matches!(capped_line(&mut reader,&mut line),Ok(1..)) {break}
+        let line = line.strip_suffix('\n').unwrap_or(&line);
+        if sender.send(line.strip_suffix('\r').unwrap_or(line).to_string()).is_err() {break}
+    }
+}
+
+//reading is blocking, so every connection gets its own thread like external bots do
+fn listen(stream:&TcpStream) -> io::Result<Receiver<String>> {
+    let reader = BufReader::new(stream.try_clone()?);
+    let (sender,lines) = channel();
+    thread::spawn(move || forward(reader,sender));
+    Ok(lines)
+}
+
+fn points<'a>(points:impl Iterator<Item = &'a Pos>) -> String {
+    let line = points.map(|p| format!("{},{}",p.x,p.y)).collect::<Vec<String>>().join(";");
+    if line.is_empty() {"-".to_string()} else {line}
+}
+
+fn parse_points(line:&str) -> Option<Vec<Pos>> {
+    if line == "-" {return Some(vec![])}
+    line.split(';').map(|point| {
+        let (x,y) = point.split_once(',')?;
+        let pos = Pos {x:x.parse().ok()?,y:y.parse().ok()?};
+        if pos.x as usize >= GAME_FIELD_SIZE || pos.y as usize >= GAME_FIELD_SIZE {return None}
+        Some(pos)
+    }).collect()
+}
+
+//"state <tick> <fruit cells> <obstacle cells> <snake>...", snake is "<alive>:<score>:<direction>:<cells from head>"
+pub fn state_line(game:&Game) -> String {
+    let obstacles = game.obstacles.iter().flat_map(|o| o.cells()).collect::<Vec<Pos>>();
+    let snakes = game.snakes.iter().map(|snake| format!(
+        "{}:{}:{}:{}",
+        snake.alive as u8,
+        snake.score,
+        direction_name(&snake.direction),
+        points(std::iter::once(&snake.pos).chain(snake.body.iter().rev()))
+    )).collect::<Vec<String>>();
+    format!(
+        "state {} {} {} {}",
+        game.tick,
+        points(std::iter::once(&game.fruit.pos).chain(&game.leftovers)),
+        points(obstacles.iter()),
+        snakes.join(" ")
+    )
+}
+
+pub fn parse_state(line:&str) -> Option<State> {
+    let mut words = line.strip_prefix("state ")?.split_whitespace();
+    let tick = words.next()?.parse().ok()?;
+    let fruits = parse_points(words.next()?)?;
+    let obstacles = parse_points(words.next()?)?;
+    let snakes = words.map(|word| {
+        let mut fields = word.splitn(4,':');
+        let alive = fields.next()? == "1";
+        let score = fields.next()?.parse().ok()?;
+        let direction = parse_direction(fields.next()?)?;
+        let mut body = parse_points(fields.next()?)?.into_iter().rev().collect::<VecDeque<Pos>>();
+        let pos = body.pop_back()?;
+        Some(Snake {
+            pos,
+            body,
+            prev_move:direction.copy(),
+            last_input:direction.copy(),
+            direction,
+            alive,
+            score
+        })
+    }).collect::<Option<Vec<Snake>>>()?;
+    Some(State {tick,fruits,obstacles,snakes})
+}
+
+//"walls <row>/<row>/...", 1 is a wall
+pub fn walls_line(walls:&Walls) -> String {
+    let rows = walls.iter()
+        .map(|line| line.iter().map(|wall| if *wall {'1'} else {'0'}).collect::<String>())
+        .collect::<Vec<String>>();
+    format!("walls {}",rows.join("/"))
+}
+
+fn parse_walls(line:&str) -> Option<Walls> {
+    let mut walls = [[false;GAME_FIELD_SIZE];GAME_FIELD_SIZE];
+    let rows = line.split('/').collect::<Vec<&str>>();
+    if rows.len() != GAME_FIELD_SIZE {return None}
+    for (y,row) in rows.iter().enumerate() {
+        if row.len() != GAME_FIELD_SIZE {return None}
+        for (x,c) in row.chars().enumerate() {
+            walls[y][x] = c == '1';
+        }
+    }
+    Some(walls)
+}
+
+//names have to fit on the screen and in one word of the message
+pub fn clean_name(name:&str) -> Option<String> {
+    let name = name.split_whitespace().collect::<Vec<&str>>().join("_").chars().take(NAME_LENGTH).collect::<String>();
+    if name.is_empty() {None} else {Some(name)}
+}
+
+pub fn default_name(player:usize) -> String {
+    format!("Player_{}",player + 1)
+}
+
+//"names <name of every player>"
+pub fn names_line(names:&[String]) -> String {
+    format!("names {}",names.join(" "))
+}
+
+//"over <winner or -> <rounds won by every player>"
+pub fn over_line(winner:Option<usize>,wins:&[u16]) -> String {
+    format!(
+        "over {} {}",
+        winner.map(|w| w.to_string()).unwrap_or("-".to_string()),
+        wins.iter().map(|w| w.to_string()).collect::<Vec<String>>().join(" ")
+    )
+}
+
+//somebody who has connected and said who they are
+struct Hello {
+    stream:TcpStream,
+    lines:Receiver<String>,
+    name:Option<String>,
+    spectator:bool
+}
+
+//waiting for the first line would stop the game, so it's done on the connection's own thread,
+//which goes on reading after that
+fn greet(stream:TcpStream,arrivals:Sender<Hello>) {
+    thread::spawn(move || {
+        let ready = stream.set_nonblocking(false)
+            .and_then(|_| stream.set_nodelay(true))
+            .and_then(|_| stream.set_write_timeout(Some(Duration::from_millis(WRITE_TIMEOUT))))
+            .and_then(|_| stream.set_read_timeout(Some(Duration::from_millis(HELLO_TIMEOUT))));
+        let Ok(mut reader) = ready.and_then(|_| stream.try_clone()).map(BufReader::new) else {return};
+        //first line is "join <name>" or "watch"
+        let mut hello = String::new();
+        if !matches!(capped_line(&mut reader,&mut hello),Ok(1..)) {return}
+        let hello = hello.trim_end();
+        let (spectator,name) = match hello.split_once(' ').unwrap_or((hello,"")) {
+            ("join",name) => (false,clean_name(name)),
+            ("watch",_) => (true,None),
+            _ => return
+        };
+        if stream.set_read_timeout(None).is_err() {return}
+        let (sender,lines) = channel();
+        if arrivals.send(Hello {stream,lines,name,spectator}).is_err() {return}
+        forward(reader,sender);
+    });
+}
+
+struct Remote {
+    stream:TcpStream,
+    lines:Receiver<String>,
+    name:Option<String>,
+    spectator:bool, //only watches, whatever it sends is ignored
+    player:Option<usize>, //None until the next round starts
+    wins:u16,
+    connected:bool
+}
+
+impl Remote {
+    fn send(&mut self,line:&str) {
+        if !self.connected {return} //don't wait for the write timeout again
+        if writeln!(self.stream,"{}",line).and_then(|_| self.stream.flush()).is_err() {
+            self.connected = false;
+        }
+    }
+}
+
+//hosting side, the only one who runs the game, everybody else just sends keys and draws what they get
+pub struct Server {
+    listener:TcpListener,
+    address:String,
+    port:u16,
+    name:String,
+    arrivals:Receiver<Hello>,
+    greeter:Sender<Hello>,
+    remotes:Vec<Remote>,
+    recorder:Option<Recorder>
+}
+
+impl Server {
+    pub fn bind(address:&str,port:u16,name:String,recorder:Option<Recorder>) -> io::Result<Server> {
+        let listener = TcpListener::bind((address,port))?;
+        listener.set_nonblocking(true)?;
+        let (greeter,arrivals) = channel();
+        Ok(Server {listener,address:address.to_string(),port,name,arrivals,greeter,remotes:vec![],recorder})
+    }
+
+    fn players(&self) -> usize {
+        self.remotes.iter().filter(|r| !r.spectator).count() + 1
+    }
+
+    //everybody who plays this round, host goes first
+    fn names(&self) -> Vec<String> {
+        let mut names = vec![self.name.clone()];
+        for remote in &self.remotes {
+            if let Some(player) = remote.player {
+                names.resize(names.len().max(player + 1),String::new());
+                names[player] = remote.name.clone().unwrap_or(default_name(player));
+            }
+        }
+        names
+    }
+
+    //spectators and the replay file get everything players get
+    fn broadcast(&mut self,line:&str) {
+        for remote in &mut self.remotes {
+            remote.send(line);
+        }
+        if let Some(recorder) = &mut self.recorder {
+            recorder.write(line);
+        }
+    }
+
+    //new players wait for the next round, but get the whole picture right away
+    fn accept(&mut self,game:Option<&Game>) {
+        while let Ok((stream,_)) = self.listener.accept() {
+            greet(stream,self.greeter.clone());
+        }
+        while let Ok(Hello {mut stream,lines,name,spectator}) = self.arrivals.try_recv() {
+            if !spectator && self.players() >= MAX_PLAYERS {
+                writeln!(stream,"bye full").ok();
+                continue
+            }
+            let mut remote = Remote {stream,lines,name,spectator,player:None,wins:0,connected:true};
+            match game {
+                Some(game) => {
+                    remote.send("round -");
+                    remote.send(&names_line(&self.names()));
+                    remote.send(&walls_line(&game.walls));
+                    remote.send(&state_line(game));
+                },
+                None => remote.send("lobby")
+            }
+            self.remotes.push(remote);
+        }
+    }
+
+    //latest direction of every player since the last tick
+    fn receive(&mut self,players:usize) -> Vec<Option<Direction>> {
+        let mut inputs = (0..players).map(|_| None).collect::<Vec<Option<Direction>>>();
+        for remote in &mut self.remotes {
+            loop {
+                match remote.lines.try_recv() {
+                    Ok(line) => if let (Some(player),Some(dir)) = (remote.player,parse_direction(line.trim())) {
+                        if player < players {inputs[player] = Some(dir)}
+                    },
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        remote.connected = false;
+                        break
+                    }
+                }
+            }
+        }
+        inputs
+    }
+
+    //returns players who have left, so their snakes can be removed from the game
+    fn forget_disconnected(&mut self) -> Vec<usize> {
+        let left = self.remotes.iter().filter(|r| !r.connected).filter_map(|r| r.player).collect();
+        self.remotes.retain(|r| r.connected);
+        left
+    }
+}
+
+fn draw_lobby(server:&Server) {
+    let watching = server.remotes.iter().filter(|r| r.spectator).count();
+    queue!(
+        stdout(),
+        Clear(ClearType::All),
+        MoveTo(GAME_FIELD_OFFSET_X,GAME_FIELD_OFFSET_Y),
+        Print(if server.address == ANY_ADDRESS {
+            format!("Hosting on port {}, every network interface",server.port)
+        } else {
+            format!("Hosting on {}:{}",server.address,server.port)
+        }),
+        MoveTo(GAME_FIELD_OFFSET_X,GAME_FIELD_OFFSET_Y + 2),
+        Print(format!("Players: {}/{}, watching: {}",server.players(),MAX_PLAYERS,watching)),
+        MoveTo(GAME_FIELD_OFFSET_X,GAME_FIELD_OFFSET_Y + 4),
+        Print(if server.players() < 2 {"Waiting for somebody to join..."} else {"ENTER - start"}),
+        MoveTo(GAME_FIELD_OFFSET_X,GAME_FIELD_OFFSET_Y + 5),
+        Print("ESC - quit")
+    ).handle();
+    stdout().flush().handle();
+}
+
+fn clear_hint() {
+    execute!(
+        stdout(),
+        MoveTo(27,3),
+        Clear(ClearType::UntilNewLine)
+    ).handle();
+}
+
+//`--host` - hosting terminal plays with arrows or WASD, others join with `--join <address>`
+pub fn host(mut server:Server,game_field:&str,map:&Map,shrink:u32,theme:&Theme) {
+    let mut joined = (usize::MAX,usize::MAX);
+    loop {
+        server.accept(None);
+        server.receive(0);
+        server.forget_disconnected();
+        if joined != (server.players(),server.remotes.len()) {
+            joined = (server.players(),server.remotes.len());
+            draw_lobby(&server);
+        }
+        match game_input(&mut [],Duration::from_millis(POLL)) {
+            InputResult::Abort => return server.broadcast("bye"),
+            InputResult::Click if server.players() > 1 => break,
+            _ => ()
+        }
+    }
+
+    let mut host_wins = 0u16;
+    loop {
+        let players = server.players();
+        for (i,remote) in server.remotes.iter_mut().filter(|r| !r.spectator).enumerate() {
+            remote.player = Some(i + 1);
+        }
+        let mut game = start_game(game_field,map,players,Rules {corpses:Corpses::Vanish,shrink,..Rules::default()},theme);
+        let names = server.names();
+        for remote in &mut server.remotes {
+            match remote.player {
+                Some(player) => remote.send(&format!("round {}",player)),
+                None => remote.send("round -")
+            }
+        }
+        if let Some(recorder) = &mut server.recorder {
+            recorder.write("round -");
+        }
+        server.broadcast(&names_line(&names));
+        server.broadcast(&walls_line(&game.walls));
+        server.broadcast(&state_line(&game));
+        wait_for_start();
+        clear_hint();
+        server.receive(players); //keys pressed before start don't count
+
+        let mut tick = SystemTime::now();
+        let time = SystemTime::now();
+        let mut over = false;
+        loop {
+            let elapsed = tick.elapsed().unwrap().as_millis();
+            let wait = Duration::from_millis(GAME_TICK.saturating_sub(elapsed as u64).min(POLL));
+            let input = game_input(&mut game.snakes[..1],wait); //host is always the first player
+            if let InputResult::Abort = input {
+                return server.broadcast("bye")
+            }
+            server.accept(Some(&game));
+            if over {
+                server.receive(0);
+                server.forget_disconnected();
+                if let InputResult::Click = input {break}
+                continue
+            }
+            if elapsed < GAME_TICK as u128 {continue}
+            tick = SystemTime::now();
+            let inputs = server.receive(players);
+            for player in server.forget_disconnected() {
+                game.leave(player); //left the game, so lost it
+            }
+            let step = game.step(&inputs);
+            draw_step(&game,&step,theme);
+            queue!(
+                stdout(),
+                MoveTo(GAME_TIME_OFFSET,GLOBAL_OFFSET_Y),
+                Print(time.elapsed().unwrap().as_secs())
+            ).handle();
+            if !step.walls_added.is_empty() {
+                server.broadcast(&walls_line(&game.walls));
+            }
+            server.broadcast(&state_line(&game));
+            if game.alive() < players.min(2) {
+                over = true;
+                let winner = if players > 1 {game.snakes.iter().position(|snake| snake.alive)} else {None};
+                let mut wins = vec![0;players];
+                if winner == Some(0) {host_wins += 1}
+                wins[0] = host_wins;
+                for remote in &mut server.remotes {
+                    let Some(player) = remote.player else {continue};
+                    if winner == Some(player) {remote.wins += 1}
+                    wins[player] = remote.wins;
+                }
+                draw_round_over(winner,&wins,&HOST_HINTS,theme);
+                server.broadcast(&over_line(winner,&wins));
+            }
+            stdout().flush().handle();
+        }
+    }
+}
+
+//client side of the network game
+pub struct Connection {
+    stream:TcpStream,
+    lines:Receiver<String>,
+    spectator:bool
+}
+
+impl Connection {
+    //name is None for spectators
+    pub fn connect(address:&str,name:Option<&str>) -> io::Result<Connection> {
+        //port can be skipped
+        let mut stream = if address.contains(':') {
+            TcpStream::connect(address)?
+        } else {
+            TcpStream::connect((address,PORT))?
+        };
+        stream.set_nodelay(true)?;
+        match name {
+            Some(name) => writeln!(stream,"join {}",clean_name(name).unwrap_or_default())?,
+            None => writeln!(stream,"watch")?
+        }
+        let lines = listen(&stream)?;
+        Ok(Connection {stream,lines,spectator:name.is_none()})
+    }
+}
+
+fn host_left(reason:&str,theme:&Theme) {
+    execute!(
+        stdout(),
+        MoveTo(27,3),
+        Clear(ClearType::UntilNewLine),
+        Print(format!("{} Press any key to quit",reason).with(theme.bad))
+    ).handle();
+    wait_for_start();
+}
+
+//draws whatever host says, the same for players, spectators and replay files
+struct Viewer<'a> {
+    game_field:&'a str,
+    theme:&'a Theme,
+    spectator:bool,
+    walls:Walls,
+    names:Vec<String>
+}
+
+impl Viewer<'_> {
+    //Some(reason) when there is nothing to watch anymore
+    fn show(&mut self,line:&str) -> Option<&'static str> {
+        let (command,rest) = line.split_once(' ').unwrap_or((line,""));
+        match command {
+            "round" => {
+                queue!(stdout(),Clear(ClearType::All)).handle();
+                draw_simple_ascii_picture(self.game_field,1,1);
+                queue!(stdout(),MoveTo(27,3),Clear(ClearType::UntilNewLine)).handle();
+                match rest.parse::<usize>() {
+                    Ok(player) => queue!(
+                        stdout(),
+                        Print(format!("You are player {}",player + 1).with(player_color(player,self.theme)))
+                    ).handle(),
+                    Err(_) if self.spectator => queue!(stdout(),Print("Spectating")).handle(),
+                    Err(_) => queue!(stdout(),Print("Watching, you play from the next round")).handle()
+                }
+            },
+            "names" => self.names = rest.split_whitespace().map(|name| name.to_string()).collect(),
+            "walls" => if let Some(walls) = parse_walls(rest) {
+                self.walls = walls;
+                draw_map(&self.walls,GAME_FIELD_OFFSET_X,GAME_FIELD_OFFSET_Y,self.theme);
+            },
+            "state" => if let Some(state) = parse_state(line) {
+                draw_state(&self.walls,&state,self.theme);
+                self.draw_names(&state);
+            },
+            "over" => {
+                let mut words = rest.split_whitespace();
+                let winner = words.next().and_then(|w| w.parse().ok());
+                let wins = words.filter_map(|w| w.parse().ok()).collect::<Vec<u16>>();
+                draw_round_over(winner,&wins,if self.spectator {&SPECTATOR_HINTS} else {&CLIENT_HINTS},self.theme);
+            },
+            "bye" => return Some(if rest == "full" {"Game is full!"} else {"Host has left!"}),
+            _ => ()
+        }
+        None
+    }
+
+    //who is who, with their scores
+    fn draw_names(&self,state:&State) {
+        for (player,snake) in state.snakes.iter().enumerate() {
+            let name = self.names.get(player).cloned().unwrap_or(default_name(player));
+            queue!(
+                stdout(),
+                MoveTo(GAME_INFO_X,NAMES_OFFSET_Y + player as u16),
+                Clear(ClearType::UntilNewLine),
+                Print(format!(
+                    "{:<width$} {:>4}{}",
+                    name,snake.score,if snake.alive {""} else {" dead"},width = NAME_LENGTH
+                ).with(player_color(player,self.theme)))
+            ).handle();
+        }
+    }
+}
+
+//reads lines from the host or a replay file and draws them,
+//keys are sent back only when there is somebody to send them to
+fn watch(lines:&Receiver<String>,mut keys:Option<&mut TcpStream>,viewer:&mut Viewer) {
+    loop {
+        let (input,dir) = direction_input(Duration::from_millis(POLL));
+        if let InputResult::Abort = input {return}
+        if let (Some(stream),Some(dir)) = (&mut keys,dir) {
+            if writeln!(stream,"{}",direction_name(&dir)).is_err() {
+                return host_left("Connection lost!",viewer.theme)
+            }
+        }
+        loop {
+            let line = match lines.try_recv() {
+                Ok(line) => line,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return host_left("Connection lost!",viewer.theme)
+            };
+            if let Some(reason) = viewer.show(&line) {
+                return host_left(reason,viewer.theme)
+            }
+        }
+        stdout().flush().handle();
+    }
+}
+
+//`--join <address>` - keys go to the host, whatever host says gets drawn,
+//`--watch <address>` is the same, but keys go nowhere
+pub fn join(mut connection:Connection,game_field:&str,theme:&Theme) {
+    execute!(
+        stdout(),
+        Clear(ClearType::All),
+        MoveTo(GAME_FIELD_OFFSET_X,GAME_FIELD_OFFSET_Y),
+        Print("Connected, waiting for host to start...")
+    ).handle();
+    let mut viewer = Viewer {
+        game_field,
+        theme,
+        spectator:connection.spectator,
+        walls:[[false;GAME_FIELD_SIZE];GAME_FIELD_SIZE],
+        names:vec![]
+    };
+    let keys = if connection.spectator {None} else {Some(&mut connection.stream)};
+    watch(&connection.lines,keys,&mut viewer);
+}
+
+//`--watch-file <file>` - follows the replay file, even if the game is still being written there
+pub fn watch_file(lines:Receiver<String>,game_field:&str,theme:&Theme) {
+    execute!(
+        stdout(),
+        Clear(ClearType::All),
+        MoveTo(GAME_FIELD_OFFSET_X,GAME_FIELD_OFFSET_Y),
+        Print("Waiting for the game to start...")
+    ).handle();
+    let mut viewer = Viewer {
+        game_field,
+        theme,
+        spectator:true,
+        walls:[[false;GAME_FIELD_SIZE];GAME_FIELD_SIZE],
+        names:vec![]
+    };
+    watch(&lines,None,&mut viewer);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_lines_are_refused() {
+        let text = format!("join ann\r\n{}\n",".".repeat(MAX_LINE));
+        let mut reader = text.as_bytes();
+        let mut line = String::new();
+        assert_eq!(capped_line(&mut reader,&mut line).unwrap(),10);
+        assert_eq!(line,"join ann\r\n");
+        assert!(capped_line(&mut reader,&mut String::new()).is_err());
+
+        let text = format!("{}\n",".".repeat(MAX_LINE - 1));
+        assert_eq!(capped_line(&mut text.as_bytes(),&mut String::new()).unwrap(),MAX_LINE);
+    }
+}
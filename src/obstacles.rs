@@ -0,0 +1,227 @@
+use crate::{
+    Pos,
+    GAME_FIELD_SIZE,
+    map::Walls
+};
+
+//bar directions, the bar is symmetric so 4 of them are enough for full rotation
+const BAR_DIRECTIONS:[(i32,i32);4] = [(1,0),(1,1),(0,1),(-1,1)];
+
+#[derive(Clone,PartialEq)]
+pub enum Motion {
+    Patrol { from:Pos, to:Pos, forward:bool }, //goes from one point to another and back
+    Bounce { dx:i32, dy:i32 }, //flies until hits wall or field border
+    Bar { length:u16, angle:usize } //rotates around pos
+}
+
+#[derive(Clone,PartialEq)]
+pub struct Obstacle {
+    pub pos:Pos,
+    pub motion:Motion,
+    pub period:u16, //ticks between moves, 1 = move every tick
+    timer:u16
+}
+
+pub trait ObstacleFunctionality {
+    fn parse(line:&str,walls:&Walls) -> Option<Self> where Self: Sized;
+    fn to_line(&self) -> String;
+    fn fits(&self,walls:&Walls) -> bool;
+    fn advance(&mut self,walls:&Walls);
+    fn cells(&self) -> Vec<Pos>;
+    fn is_in_point(&self,x:u16,y:u16) -> bool;
+}
+
+fn in_field(x:i32,y:i32) -> bool {
+    x >= 0 && y >= 0 && x < GAME_FIELD_SIZE as i32 && y < GAME_FIELD_SIZE as i32
+}
+
+fn is_free(walls:&Walls,x:i32,y:i32) -> bool {
+    in_field(x,y) && !walls[y as usize][x as usize]
+}
+
+impl ObstacleFunctionality for Obstacle {
+    //formats (period is optional):
+    //patrol x1 y1 x2 y2 [period]
+    //bounce x y dx dy [period]
+    //bar x y length [period]
+    fn parse(line:&str,walls:&Walls) -> Option<Obstacle> {
+        let mut words = line.split_whitespace();
+        let kind = words.next()?;
+        let args = words.map(|w| w.parse::<i32>().ok()).collect::<Option<Vec<i32>>>()?;
+        let field = 0..GAME_FIELD_SIZE as i32;
+        let pos = |x:i32,y:i32| -> Option<Pos> {
+            if field.contains(&x) && field.contains(&y) {
+                Some(Pos{x:x as u16,y:y as u16})
+            } else {None}
+        };
+        let (pos,motion,rest) = match kind {
+            "patrol" if args.len() >= 4 => {
+                let from = pos(args[0],args[1])?;
+                let to = pos(args[2],args[3])?;
+                (from,Motion::Patrol{from,to,forward:true},&args[4..])
+            },
+            "bounce" if args.len() >= 4 => (
+                pos(args[0],args[1])?,
+                Motion::Bounce{dx:args[2].signum(),dy:args[3].signum()},
+                &args[4..]
+            ),
+            "bar" if args.len() >= 3 && args[2] > 0 => (
+                pos(args[0],args[1])?,
+                Motion::Bar{length:args[2] as u16,angle:0},
+                &args[3..]
+            ),
+            _ => return None
+        };
+        let period = match rest.first() {
+            Some(p) if *p > 0 => u16::try_from(*p).ok()?,
+            Some(_) => return None,
+            None => 1
+        };
+        let obstacle = Obstacle {pos,motion,period,timer:0};
+        //it can't start in a wall, and patrol walks straight through them, so its way has to be free too
+        let mut cells = obstacle.cells();
+        if let Motion::Patrol{to,..} = obstacle.motion {
+            let mut walker = Obstacle {period:1,..obstacle.clone()};
+            while walker.pos != to {
+                walker.advance(walls);
+                cells.push(walker.pos);
+            }
+        }
+        if cells.iter().any(|cell| walls[cell.y as usize][cell.x as usize]) {return None}
+        Some(obstacle)
+    }
+
+    fn to_line(&self) -> String {
+        let line = match &self.motion {
+            Motion::Patrol{from,to,..} => format!("patrol {} {} {} {}",from.x,from.y,to.x,to.y),
+            Motion::Bounce{dx,dy} => format!("bounce {} {} {} {}",self.pos.x,self.pos.y,dx,dy),
+            Motion::Bar{length,..} => format!("bar {} {} {}",self.pos.x,self.pos.y,length)
+        };
+        if self.period == 1 {line} else {format!("{} {}",line,self.period)}
+    }
+
+    //whether it would still be there after the map is saved and loaded with these walls
+    fn fits(&self,walls:&Walls) -> bool {
+        Obstacle::parse(&self.to_line(),walls).is_some()
+    }
+
+    fn advance(&mut self,walls:&Walls) {
+        self.timer += 1;
+        if self.timer < self.period {return}
+        self.timer = 0;
+        let (x,y) = (self.pos.x as i32,self.pos.y as i32);
+        match &mut self.motion {
+            Motion::Patrol{from,to,forward} => {
+                let target = if *forward {*to} else {*from};
+                let nx = x + (target.x as i32 - x).signum();
+                let ny = y + (target.y as i32 - y).signum();
+                self.pos = Pos{x:nx as u16,y:ny as u16};
+                if self.pos == target {*forward = !*forward}
+            },
+            Motion::Bounce{dx,dy} => {
+                if !is_free(walls,x + *dx,y) {*dx = -*dx}
+                if !is_free(walls,x,y + *dy) {*dy = -*dy}
+                if is_free(walls,x + *dx,y + *dy) {
+                    self.pos = Pos{x:(x + *dx) as u16,y:(y + *dy) as u16};
+                } else { //stuck in the corner, go back where it came from
+                    *dx = -*dx;
+                    *dy = -*dy;
+                }
+            },
+            Motion::Bar{angle,..} => *angle = (*angle + 1) % BAR_DIRECTIONS.len()
+        }
+    }
+
+    fn cells(&self) -> Vec<Pos> {
+        let mut cells = vec![self.pos];
+        if let Motion::Bar{length,angle} = &self.motion {
+            let (vx,vy) = BAR_DIRECTIONS[*angle];
+            for k in 1..=*length as i32 {
+                for side in [1,-1] {
+                    let x = self.pos.x as i32 + vx * k * side;
+                    let y = self.pos.y as i32 + vy * k * side;
+                    if in_field(x,y) {cells.push(Pos{x:x as u16,y:y as u16})}
+                }
+            }
+        }
+        cells
+    }
+
+    fn is_in_point(&self,x:u16,y:u16) -> bool {
+        self.cells().iter().any(|cell| cell.x == x && cell.y == y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::reset_map;
+
+    fn bordered() -> Walls {
+        let mut walls = [[false;GAME_FIELD_SIZE];GAME_FIELD_SIZE];
+        reset_map(&mut walls);
+        walls
+    }
+
+    fn parse(line:&str) -> Option<Obstacle> {
+        Obstacle::parse(line,&bordered())
+    }
+
+    #[test]
+    fn parses_and_writes_back() {
+        for line in ["patrol 2 2 5 2","bounce 4 4 1 -1 3","bar 10 10 2"] {
+            assert_eq!(parse(line).unwrap().to_line(),line);
+        }
+        let bounce = parse("bounce 4 4 7 -9").unwrap(); //only the sign of the speed counts
+        assert!(bounce.motion == Motion::Bounce {dx:1,dy:-1} && bounce.period == 1);
+        for line in ["patrol 2 2 5","bar 3 3 0","bounce 4 4 1 1 0","bounce 4 4 1 1 65537","spin 4 4 1","bounce 0 4 1 1","patrol 2 2 2 40","bounce a 4 1 1"] {
+            assert!(parse(line).is_none(),"{}",line);
+        }
+        //its way crosses a wall in the middle of the field
+        let mut walls = bordered();
+        walls[5][5] = true;
+        assert!(Obstacle::parse("patrol 2 5 8 5",&walls).is_none());
+        assert!(Obstacle::parse("patrol 2 6 8 6",&walls).is_some());
+    }
+
+    #[test]
+    fn patrols_back_and_forth() {
+        let walls = bordered();
+        let mut patrol = parse("patrol 2 2 4 4").unwrap();
+        let mut way = vec![];
+        for _ in 0..4 {
+            patrol.advance(&walls);
+            way.push(patrol.pos);
+        }
+        assert!(way == [Pos {x:3,y:3},Pos {x:4,y:4},Pos {x:3,y:3},Pos {x:2,y:2}]);
+    }
+
+    #[test]
+    fn bounces_off_walls() {
+        let walls = bordered();
+        let mut bounce = parse("bounce 17 10 1 1").unwrap();
+        bounce.advance(&walls);
+        assert!(bounce.pos == Pos {x:18,y:11});
+        bounce.advance(&walls); //border is at 19
+        assert!(bounce.pos == Pos {x:17,y:12} && bounce.motion == Motion::Bounce {dx:-1,dy:1});
+        //straight into the corner and back the same way
+        let mut bounce = parse("bounce 18 18 1 1").unwrap();
+        bounce.advance(&walls);
+        assert!(bounce.pos == Pos {x:17,y:17} && bounce.motion == Motion::Bounce {dx:-1,dy:-1});
+    }
+
+    #[test]
+    fn bar_turns_and_waits() {
+        let walls = bordered();
+        let mut bar = parse("bar 10 10 1 2").unwrap();
+        assert!(bar.cells() == [Pos {x:10,y:10},Pos {x:11,y:10},Pos {x:9,y:10}]);
+        bar.advance(&walls);
+        assert_eq!(bar.cells().len(),3);
+        assert!(bar.is_in_point(11,10)); //period 2, so it didn't move yet
+        bar.advance(&walls);
+        assert!(bar.cells() == [Pos {x:10,y:10},Pos {x:11,y:11},Pos {x:9,y:9}]);
+        //cells out of the field are cut off
+        let bar = Obstacle::parse("bar 1 1 3",&[[false;GAME_FIELD_SIZE];GAME_FIELD_SIZE]).unwrap();
+        assert_eq!(bar.cells().len(),5);
+    }
+}
@@ -0,0 +1,225 @@
+use std::{
+    io::{Write,stdout},
+    fs,
+    time::{SystemTime,Duration}
+};
+
+use crossterm::{
+    execute, queue,
+    terminal::{Clear,ClearType},
+    cursor::MoveTo,
+    style::{Print,Stylize}
+};
+
+use crate::{
+    GAME_TICK,
+    GAME_TIME_OFFSET,
+    GAME_FIELD_SIZE,
+    GAME_FIELD_OFFSET_Y,
+    GAME_INFO_X,
+    GLOBAL_OFFSET_Y,
+    PUZZLES_DIR,
+    DirectionFunctionality,
+    start_game,
+    draw_step,
+    draw_map,
+    draw_box,
+    wait_for_start,
+    confirm,
+    game_seconds,
+    general::{error_handling::*,input::*,list::*},
+    engine::{Game,GameFunctionality,Rules},
+    map::{Map,MapFunctionality,list_txt_in},
+    scores::Scores,
+    net::default_name,
+    ghost::{Ghost,Run},
+    theme::Theme
+};
+
+const NAME_WIDTH:usize = 16;
+const PREVIEW_X:u16 = LIST_X + 48;
+const HINTS:&str = "Arrows, Mouse - choose puzzle\nENTER, LMB - play it\nT - turn-based on/off\nESC - back";
+const INFO_OFFSET_Y:u16 = GAME_FIELD_OFFSET_Y + 2;
+
+//map with fruit sequence and, if there's a `moves <n>` line in its file, that many moves to eat it all
+struct Puzzle {
+    name:String,
+    map:Map,
+    moves:u32 //0 = no limit, only the fewest moves count
+}
+
+enum Outcome {
+    Solved(u32), //moves it took
+    Failed(&'static str),
+    Quit
+}
+
+impl Puzzle {
+    fn load(name:&str) -> Option<Puzzle> {
+        let text = fs::read_to_string(format!("{}/{}.txt",PUZZLES_DIR,name)).ok()?;
+        let map = Map::parse(&text);
+        if map.fruits.is_empty() {return None} //nothing to solve
+        let moves = text.lines().find_map(|line| line.strip_prefix("moves ")?.trim().parse().ok()).unwrap_or(0);
+        Some(Puzzle {name:name.to_string(),map,moves})
+    }
+
+    //turn-based and real-time solutions are ranked apart
+    fn table(&self,turns:bool) -> String {
+        format!("puzzle-{}{}",self.name,if turns {"-turns"} else {""})
+    }
+
+    fn limit_text(&self) -> String {
+        if self.moves > 0 {format!("{} moves",self.moves)} else {"no limit".to_string()}
+    }
+}
+
+fn draw_puzzles(puzzles:&[Puzzle],scores:&Scores,selected:usize,turns:bool,theme:&Theme) {
+    queue!(
+        stdout(),
+        MoveTo(LIST_X,GLOBAL_OFFSET_Y),
+        Clear(ClearType::UntilNewLine),
+        Print(format!("Puzzles   turn-based: {}",if turns {"ON"} else {"OFF"}))
+    ).handle();
+    for (row,puzzle) in puzzles.iter().enumerate() {
+        let best = scores.table(&puzzle.table(turns)).first().map_or(String::new(),|(moves,_)| format!("best {}",moves));
+        let line = format!(
+            " {:<name$} {:>2} fruit  {:<9} {:<9}",
+            puzzle.name,puzzle.map.fruits.len(),puzzle.limit_text(),best,
+            name = NAME_WIDTH
+        );
+        queue!(
+            stdout(),
+            MoveTo(LIST_X,LIST_Y + row as u16),
+            Print(if row == selected {theme.highlight(line)} else {line.reset()})
+        ).handle();
+    }
+    draw_map(&puzzles[selected].map.walls,PREVIEW_X,LIST_Y,theme);
+}
+
+//puzzle list, the chosen one is played until solved or given up
+pub fn run(game_field:&str,theme:&Theme) {
+    let puzzles = list_txt_in(PUZZLES_DIR).iter().filter_map(|name| Puzzle::load(name)).collect::<Vec<Puzzle>>();
+    if puzzles.is_empty() {
+        execute!(
+            stdout(),
+            Clear(ClearType::All),
+            MoveTo(LIST_X,GLOBAL_OFFSET_Y),
+            Print(format!("No puzzles in \"{}\", press any key",PUZZLES_DIR).with(theme.bad))
+        ).handle();
+        return wait_for_start()
+    }
+    let mut scores = Scores::load();
+    let mut selected = 0;
+    let mut turns = false;
+    loop {
+        execute!(stdout(),Clear(ClearType::All)).handle();
+        let mut list = List::new(puzzles.len(),selected);
+        list.draw_hints(HINTS,GAME_FIELD_SIZE);
+        loop {
+            draw_puzzles(&puzzles,&scores,selected,turns,theme);
+            stdout().flush().handle();
+            let input = list.input();
+            selected = list.selected;
+            match input {
+                InputResult::Click => break,
+                InputResult::Key('t' | 'T') => turns = !turns,
+                InputResult::Abort => return,
+                _ => ()
+            }
+        }
+        loop {
+            let puzzle = &puzzles[selected];
+            let last = selected + 1 == puzzles.len();
+            match play(game_field,puzzle,turns,&scores,theme) {
+                Outcome::Solved(moves) => {
+                    let best = scores.add(&puzzle.table(turns),moves,&puzzle.name,true);
+                    draw_box(
+                        "SOLVED!".to_string().with(theme.good),
+                        &[
+                            format!("{} moves{}",moves,if best {" best!"} else {""}),
+                            (if last {"ENTER - again"} else {"ENTER - next"}).to_string(),
+                            "ESC - puzzles".to_string()
+                        ],
+                        theme
+                    );
+                    if !confirm() {break}
+                    if !last {selected += 1}
+                },
+                Outcome::Failed(reason) => {
+                    draw_box(reason.to_string().with(theme.bad),&[String::new(),"ENTER - retry".to_string(),"ESC - puzzles".to_string()],theme);
+                    if !confirm() {break}
+                },
+                Outcome::Quit => break
+            }
+        }
+    }
+}
+
+fn draw_info(puzzle:&Puzzle,game:&Game,turns:bool,best:Option<u32>,ghost:&mut Option<Ghost>,theme:&Theme) {
+    let eaten = game.snakes[0].score;
+    let moves = if puzzle.moves > 0 {format!("{}/{}",game.tick,puzzle.moves)} else {game.tick.to_string()};
+    let lines = [
+        format!("Puzzle: {}{}",puzzle.name,if turns {" (turn-based)"} else {""}),
+        format!("Fruit: {}/{}",eaten,puzzle.map.fruits.len()),
+        format!("Moves: {}",moves),
+        format!("Best: {}",best.map_or("-".to_string(),|best| best.to_string()))
+    ];
+    for (row,line) in (0u16..).zip(lines) {
+        queue!(stdout(),MoveTo(GAME_INFO_X,INFO_OFFSET_Y + row),Clear(ClearType::UntilNewLine),Print(line)).handle();
+    }
+    //the best solution racing along, and how much fruit it had by now
+    if let Some(ghost) = ghost {
+        ghost.draw(game,theme);
+        queue!(
+            stdout(),
+            MoveTo(GAME_INFO_X,INFO_OFFSET_Y + 4),
+            Clear(ClearType::UntilNewLine),
+            Print(format!("Ghost fruit: {}/{}",ghost.score_at(game.tick),puzzle.map.fruits.len()).with(theme.ghost))
+        ).handle();
+    }
+    queue!(stdout(),MoveTo(GAME_TIME_OFFSET,GLOBAL_OFFSET_Y),Print(game_seconds(game))).handle();
+}
+
+//in turn-based game the snake moves only when a key is pressed, keys can't turn it back
+fn play(game_field:&str,puzzle:&Puzzle,turns:bool,scores:&Scores,theme:&Theme) -> Outcome {
+    //the last fruit doesn't come back, so there's nothing else to eat after the sequence
+    let mut game = start_game(game_field,&puzzle.map,1,Rules {fruits:1,..Rules::default()},theme);
+    let best = scores.table(&puzzle.table(turns)).first().map(|(moves,_)| *moves);
+    let mut ghost = Ghost::load(&puzzle.table(turns));
+    let mut run = Run::start(&game,&default_name(0));
+    draw_info(puzzle,&game,turns,best,&mut ghost,theme);
+    stdout().flush().handle();
+    wait_for_start();
+    execute!(stdout(),MoveTo(GAME_INFO_X,3),Clear(ClearType::UntilNewLine)).handle();
+    let mut tick = SystemTime::now();
+    loop {
+        let decision = if turns {
+            let (input,direction) = direction_input(Duration::MAX);
+            if let InputResult::Abort = input {return Outcome::Quit}
+            match direction {
+                Some(direction) if !direction.is_opposite_of(&game.snakes[0].prev_move) => Some(direction),
+                _ => continue
+            }
+        } else {
+            let elapsed = tick.elapsed().unwrap().as_millis();
+            let wait = Duration::from_millis(GAME_TICK.saturating_sub(elapsed as u64));
+            if let InputResult::Abort = game_input(&mut game.snakes[..1],wait) {return Outcome::Quit}
+            if elapsed < GAME_TICK as u128 {continue}
+            tick = SystemTime::now();
+            None
+        };
+        let step = game.step(&[decision]);
+        draw_step(&game,&step,theme);
+        draw_info(puzzle,&game,turns,best,&mut ghost,theme);
+        run.step(&game);
+        stdout().flush().handle();
+        if !game.snakes[0].alive {return Outcome::Failed("CRASHED!")}
+        if game.snakes[0].score as usize >= puzzle.map.fruits.len() {
+            if ghost.is_none_or(|ghost| game.tick < ghost.ticks()) {
+                run.save(&puzzle.table(turns));
+            }
+            return Outcome::Solved(game.tick)
+        }
+        if puzzle.moves > 0 && game.tick >= puzzle.moves {return Outcome::Failed("OUT OF MOVES")}
+    }
+}
@@ -0,0 +1,61 @@
+use std::{
+    io::{self,Write,BufRead,BufReader},
+    fs::File,
+    sync::mpsc::{channel,Receiver},
+    thread,
+    time::Duration
+};
+
+use crate::{
+    engine::Game,
+    net::{state_line,walls_line,names_line}
+};
+
+const TAIL_POLL:u64 = 50; //ms to wait for the game to write something new
+
+//writes the game in the same lines host sends over network, so the file can be watched like a host
+pub struct Recorder {
+    file:File
+}
+
+impl Recorder {
+    pub fn create(path:&str) -> io::Result<Recorder> {
+        Ok(Recorder {file:File::create(path)?})
+    }
+
+    pub fn write(&mut self,line:&str) {
+        writeln!(self.file,"{}",line).ok(); //a full disk ends the recording, not the match
+    }
+
+    pub fn round(&mut self,game:&Game,names:&[String]) {
+        for line in round_lines(game,names) {
+            self.write(&line);
+        }
+    }
+}
+
+//everything spectator needs to start watching a new round
+pub fn round_lines(game:&Game,names:&[String]) -> [String;4] {
+    ["round -".to_string(),names_line(names),walls_line(&game.walls),state_line(game)]
+}
+
+//reads file line by line and waits for more at the end, like `tail -f`
+pub fn tail(path:&str) -> io::Result<Receiver<String>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let (sender,lines) = channel();
+    thread::spawn(move || {
+        let mut line = String::new();
+        loop {
+            match reader.read_line(&mut line) {
+                Ok(0) => thread::sleep(Duration::from_millis(TAIL_POLL)),
+                Ok(_) if line.ends_with('\n') => { //half written line waits for the rest
+                    if sender.send(line.trim_end().to_string()).is_err() {break}
+                    line.clear();
+                },
+                Ok(_) => (),
+                Err(_) => break
+            }
+        }
+    });
+    Ok(lines)
+}
@@ -0,0 +1,101 @@
+use std::fs;
+
+use crate::{
+    FILE_SCORES,
+    map::write_atomically
+};
+
+pub const SCORES_KEPT:usize = 5; //per table
+
+//best scores of every game mode (or puzzle, or day) and maps they were made on, best first
+pub struct Scores {
+    entries:Vec<(String,u32,String)>
+}
+
+impl Scores {
+    //format: <table> <score> <map>
+    pub fn load() -> Scores {
+        let text = fs::read_to_string(FILE_SCORES).unwrap_or_default();
+        let entries = text.lines().filter_map(|line| {
+            let words = line.split_whitespace().collect::<Vec<&str>>();
+            let [table,score,map] = words[..] else {return None};
+            Some((table.to_string(),score.parse().ok()?,map.to_string()))
+        }).collect();
+        Scores {entries}
+    }
+
+    fn save(&self) {
+        let text = self.entries.iter().map(|(table,score,map)| format!("{} {} {}\n",table,score,map)).collect::<String>();
+        write_atomically(FILE_SCORES,&text).ok(); //the table still holds them until the game is closed
+    }
+
+    pub fn table(&self,table:&str) -> Vec<(u32,&str)> {
+        self.entries.iter()
+            .filter(|(name,_,_)| name == table)
+            .map(|(_,score,map)| (*score,map.as_str()))
+            .collect()
+    }
+
+    //(table,score,map) of every table that starts with `prefix`
+    pub fn rows(&self,prefix:&str) -> Vec<(&str,u32,&str)> {
+        self.entries.iter()
+            .filter(|(name,_,_)| name.starts_with(prefix))
+            .map(|(name,score,map)| (name.as_str(),*score,map.as_str()))
+            .collect()
+    }
+
+    //`fewest` is for tables where less is better, tells if it's the new best one
+    pub fn add(&mut self,table:&str,score:u32,map:&str,fewest:bool) -> bool {
+        let best = self.insert(table,score,map,fewest);
+        self.save();
+        best
+    }
+
+    fn insert(&mut self,table:&str,score:u32,map:&str,fewest:bool) -> bool {
+        let better = |old:u32| if fewest {score < old} else {score > old};
+        let best = self.table(table).first().map(|(best,_)| *best);
+        let place = self.entries.iter().position(|(name,old,_)| name == table && better(*old));
+        let entry = (table.to_string(),score,map.to_string());
+        match place {
+            Some(place) => self.entries.insert(place,entry),
+            None => self.entries.push(entry)
+        }
+        //worst ones fall out of the table
+        while self.table(table).len() > SCORES_KEPT {
+            let last = self.entries.iter().rposition(|(name,_,_)| name == table).unwrap();
+            self.entries.remove(last);
+        }
+        best.is_none_or(better)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_best() {
+        let mut scores = Scores {entries:vec![]};
+        assert!(scores.insert("zen",5,"a",false));
+        assert!(!scores.insert("zen",3,"b",false));
+        assert!(scores.insert("zen",8,"c",false));
+        assert!(!scores.insert("zen",8,"d",false)); //a tie isn't a new best, and goes after the old one
+        assert!(scores.table("zen") == [(8,"c"),(8,"d"),(5,"a"),(3,"b")]);
+        //other tables aren't touched, and where less is better it goes the other way
+        assert!(scores.insert("puzzle",20,"p",true) && scores.insert("puzzle",12,"p",true));
+        assert!(scores.table("puzzle") == [(12,"p"),(20,"p")] && scores.table("zen").len() == 4);
+    }
+
+    #[test]
+    fn forgets_the_worst() {
+        let mut scores = Scores {entries:vec![]};
+        for score in 1..=SCORES_KEPT as u32 {
+            scores.insert("time60",score,"m",false);
+        }
+        assert!(!scores.insert("time60",0,"m",false));
+        assert!(scores.insert("time60",100,"m",false));
+        let table = scores.table("time60");
+        assert!(table.len() == SCORES_KEPT && table[0].0 == 100 && table.last().unwrap().0 == 2);
+        assert!(scores.rows("time").len() == SCORES_KEPT && scores.rows("zen").is_empty());
+    }
+}
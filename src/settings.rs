@@ -0,0 +1,198 @@
+use std::{
+    io::{Write,stdout},
+    fs
+};
+
+use crossterm::{
+    execute, queue,
+    terminal::{Clear,ClearType},
+    cursor::MoveTo,
+    style::{Print,Stylize}
+};
+
+use crate::{
+    GLOBAL_OFFSET_Y,
+    THEMES_DIR,
+    FILE_SETTINGS,
+    flag,
+    general::{error_handling::*,input::*,list::*},
+    map::{list_txt_in,write_atomically},
+    theme::{Theme,Colors,DEFAULT_THEME,unicode_locale,terminal_colors}
+};
+
+const NAME_WIDTH:usize = 12;
+const VALUE_WIDTH:usize = 16;
+const SAMPLE_X:u16 = LIST_X + 40;
+const GLYPHS:[&str;3] = ["auto","unicode","ascii"]; //auto goes by the locale
+const COLORS:[&str;5] = ["auto","truecolor","256","16","none"]; //auto goes by the terminal and NO_COLOR
+const HINTS:&str = "Arrows, Mouse - choose setting\nLEFT, RIGHT, ENTER, LMB - change it\nESC - back";
+//# wall, . field, - | F 7 L J body, h head, * fruit, o obstacle, G H ghost body and head
+const SAMPLE:[&str;6] = [
+    "############",
+    "#..F---7..*#",
+    "#..|...|...#",
+    "#--J.o.L--h#",
+    "#.GGH......#",
+    "############"
+];
+
+//whatever was chosen last time, one `<setting> <value>` line each
+pub struct Settings {
+    pub theme:String,
+    pub glyphs:String,
+    pub colors:String
+}
+
+impl Settings {
+    pub fn load() -> Settings {
+        let text = fs::read_to_string(FILE_SETTINGS).unwrap_or_default();
+        let mut settings = Settings {theme:DEFAULT_THEME.to_string(),glyphs:GLYPHS[0].to_string(),colors:COLORS[0].to_string()};
+        for line in text.lines() {
+            match line.split_once(' ') {
+                Some(("theme",name)) => settings.theme = name.trim().to_string(),
+                Some(("glyphs",glyphs)) => settings.glyphs = glyphs.trim().to_string(),
+                Some(("colors",colors)) => settings.colors = colors.trim().to_string(),
+                _ => ()
+            }
+        }
+        settings
+    }
+
+    fn save(&self) {
+        let text = format!("theme {}\nglyphs {}\ncolors {}\n",self.theme,self.glyphs,self.colors);
+        write_atomically(FILE_SETTINGS,&text).ok(); //it's chosen for now anyway
+    }
+
+    //--ascii is for terminals that can't show anything else, whatever was chosen
+    pub fn ascii(&self) -> bool {
+        flag("--ascii") || match self.glyphs.as_str() {
+            "ascii" => true,
+            "unicode" => false,
+            _ => !unicode_locale()
+        }
+    }
+
+    //--no-color is the same as NO_COLOR, but it wins over the settings too
+    pub fn colors(&self) -> Colors {
+        if flag("--no-color") {return Colors::None}
+        match self.colors.as_str() {
+            "truecolor" => Colors::True,
+            "256" => Colors::Ansi256,
+            "16" => Colors::Ansi16,
+            "none" => Colors::None,
+            _ => terminal_colors()
+        }
+    }
+}
+
+//one row of the screen, its values go round
+struct Setting {
+    name:&'static str,
+    values:Vec<String>,
+    chosen:usize
+}
+
+impl Setting {
+    fn new(name:&'static str,values:Vec<String>,current:&str) -> Setting {
+        let chosen = values.iter().position(|value| value == current).unwrap_or(0);
+        Setting {name,values,chosen}
+    }
+
+    fn value(&self) -> &str {
+        &self.values[self.chosen]
+    }
+
+    fn turn(&mut self,back:bool) {
+        let count = self.values.len();
+        self.chosen = if back {(self.chosen + count - 1) % count} else {(self.chosen + 1) % count};
+    }
+}
+
+//how the game looks with the theme, every glyph and color of the field
+fn draw_sample(theme:&Theme,x:u16,y:u16) {
+    for (row,line) in (0u16..).zip(SAMPLE) {
+        queue!(stdout(),MoveTo(x,y + row)).handle();
+        for cell in line.chars() {
+            let snake = |sign:char| sign.with(theme.players[0]).on(theme.field);
+            let sign = match cell {
+                '#' => theme.wall_sign(),
+                '-' => snake(theme.body[0]),
+                '|' => snake(theme.body[1]),
+                'F' => snake(theme.body[2]),
+                '7' => snake(theme.body[3]),
+                'L' => snake(theme.body[4]),
+                'J' => snake(theme.body[5]),
+                'h' => snake(theme.heads[0]),
+                '*' => theme.fruit_sign(),
+                'o' => theme.obstacle_sign(),
+                'G' => theme.ghost_sign(theme.body[0]),
+                'H' => theme.ghost_sign(theme.heads[0]),
+                _ => ' '.on(theme.field)
+            };
+            queue!(stdout(),Print(sign)).handle();
+        }
+    }
+    let y = y + SAMPLE.len() as u16 + 1;
+    queue!(stdout(),MoveTo(x,y),Clear(ClearType::UntilNewLine)).handle();
+    for (player,color) in theme.players.iter().enumerate() {
+        queue!(stdout(),Print(format!("P{}{} ",player + 1,theme.player(player).heads[0]).with(*color))).handle();
+    }
+    queue!(
+        stdout(),
+        MoveTo(x,y + 1),
+        Clear(ClearType::UntilNewLine),
+        Print("CLEAR! ".with(theme.good)),
+        Print("OVER! ".with(theme.bad)),
+        Print("DRAW!".with(theme.draw)),
+        MoveTo(x,y + 2),
+        Clear(ClearType::UntilNewLine),
+        Print(theme.highlight(" chosen ")),
+        Print(" locked".with(theme.locked))
+    ).handle();
+}
+
+fn draw_settings(settings:&[Setting],selected:usize,theme:&Theme) {
+    queue!(stdout(),MoveTo(LIST_X,GLOBAL_OFFSET_Y),Print("Settings")).handle();
+    for (row,setting) in settings.iter().enumerate() {
+        let line = format!(" {:<name$} < {:<value$} >",setting.name,setting.value(),name = NAME_WIDTH,value = VALUE_WIDTH);
+        queue!(
+            stdout(),
+            MoveTo(LIST_X,LIST_Y + row as u16),
+            Print(if row == selected {theme.highlight(line)} else {line.reset()})
+        ).handle();
+    }
+    draw_sample(theme,SAMPLE_X,LIST_Y);
+}
+
+//every change is used and saved right away
+pub fn run(theme:&mut Theme) {
+    let mut saved = Settings::load();
+    let mut themes = list_txt_in(THEMES_DIR);
+    if themes.is_empty() {
+        themes.push(DEFAULT_THEME.to_string()); //the classic one doesn't need a file
+    }
+    let mut settings = [
+        Setting::new("Theme",themes,&saved.theme),
+        Setting::new("Glyphs",GLYPHS.map(String::from).to_vec(),&saved.glyphs),
+        Setting::new("Colors",COLORS.map(String::from).to_vec(),&saved.colors)
+    ];
+    let mut list = List::new(settings.len(),0);
+    execute!(stdout(),Clear(ClearType::All)).handle();
+    list.draw_hints(HINTS,0);
+    loop {
+        draw_settings(&settings,list.selected,theme);
+        stdout().flush().handle();
+        let back = match list.input() {
+            InputResult::Click | InputResult::Right => false,
+            InputResult::Left => true,
+            InputResult::Abort => return,
+            _ => continue
+        };
+        settings[list.selected].turn(back);
+        saved.theme = settings[0].value().to_string();
+        saved.glyphs = settings[1].value().to_string();
+        saved.colors = settings[2].value().to_string();
+        saved.save();
+        *theme = Theme::load(&saved.theme,saved.ascii(),saved.colors());
+    }
+}
@@ -0,0 +1,393 @@
+use std::{
+    fs,
+    fmt::Display
+};
+
+use crossterm::style::{Color,Stylize,StyledContent,ContentStyle};
+
+use crate::{
+    Direction,
+    MAX_PLAYERS,
+    THEMES_DIR
+};
+
+pub const DEFAULT_THEME:&str = "classic";
+//the 16 colors every terminal has and how they usually look
+const ANSI16:[(Color,(u8,u8,u8));16] = [
+    (Color::Black,(0,0,0)),
+    (Color::DarkRed,(128,0,0)),
+    (Color::DarkGreen,(0,128,0)),
+    (Color::DarkYellow,(128,128,0)),
+    (Color::DarkBlue,(0,0,128)),
+    (Color::DarkMagenta,(128,0,128)),
+    (Color::DarkCyan,(0,128,128)),
+    (Color::Grey,(192,192,192)),
+    (Color::DarkGrey,(128,128,128)),
+    (Color::Red,(255,0,0)),
+    (Color::Green,(0,255,0)),
+    (Color::Yellow,(255,255,0)),
+    (Color::Blue,(0,0,255)),
+    (Color::Magenta,(255,0,255)),
+    (Color::Cyan,(0,255,255)),
+    (Color::White,(255,255,255))
+];
+//without colors only glyphs tell the snakes apart, the first one keeps the theme's, these are heads and body of the others
+const COLORLESS_SIGNS:[([char;4],[char;6]);MAX_PLAYERS - 1] = [
+    (['▷','◁','▽','△'],['─','│','┌','┐','└','┘']),
+    (['→','←','↓','↑'],['━','┃','┏','┓','┗','┛']),
+    (['⇒','⇐','⇓','⇑'],['┄','┆','╭','╮','╰','╯'])
+];
+//plain ones would all turn into - | +, so every snake gets its own sign
+const COLORLESS_ASCII:[([char;4],[char;6]);MAX_PLAYERS - 1] = [
+    (['}','{','W','M'],['=';6]),
+    ([')','(','U','N'],['~';6]),
+    ([']','[','Y','K'],[':';6])
+];
+const CUBE_LEVELS:[u8;6] = [0,95,135,175,215,255]; //of every channel in the 6x6x6 part of 256 colors
+
+//how many colors the terminal can show
+#[derive(Clone,Copy,PartialEq)]
+pub enum Colors {
+    True,
+    Ansi256,
+    Ansi16,
+    None //NO_COLOR, only glyphs tell things apart
+}
+
+//every glyph and color the game draws with
+#[derive(Clone,Copy)]
+pub struct Theme {
+    pub field:Color, //background of every free cell
+    pub wall:char,
+    pub wall_color:Color,
+    pub wall_background:Color,
+    pub spawn:Color, //background of spawns in the editor
+    pub fruit:char,
+    pub fruit_color:Color,
+    pub obstacle:char,
+    pub obstacle_color:Color,
+    pub heads:[char;4], //right, left, down, up
+    pub body:[char;6], //═ ║ ╔ ╗ ╚ ╝ in this order
+    pub players:[Color;MAX_PLAYERS],
+    pub ghost:Color,
+    pub good:Color, //level cleared, puzzle solved
+    pub bad:Color, //game over and everything that went wrong
+    pub draw:Color,
+    pub selected:Color, //chosen row of every list
+    pub selected_background:Color,
+    pub locked:Color,
+    pub cursor:Color,
+    pub hover:Color, //cursor over a button
+    pub preview:Color, //shape or spawn that is being placed in the editor
+    pub ascii:bool, //every glyph is swapped for a plain one when drawn
+    pub colors:Colors
+}
+
+//how the game looked before there were themes, every theme file starts from it
+pub const CLASSIC:Theme = Theme {
+    field:Color::Rgb{r:40,g:40,b:40},
+    wall:' ',
+    wall_color:Color::Reset,
+    wall_background:Color::Yellow,
+    spawn:Color::Red,
+    fruit:'¤', //this sign is supportable with all fonts
+    fruit_color:Color::Red,
+    obstacle:'▓',
+    obstacle_color:Color::Magenta,
+    heads:['►','◄','▼','▲'],
+    body:['═','║','╔','╗','╚','╝'],
+    players:[Color::Green,Color::Cyan,Color::Blue,Color::White], //arrows player, WASD player, the rest join by network
+    ghost:Color::Rgb{r:90,g:90,b:90}, //barely brighter than the field
+    good:Color::Green,
+    bad:Color::Red,
+    draw:Color::Yellow,
+    selected:Color::Black,
+    selected_background:Color::Yellow,
+    locked:Color::DarkGrey,
+    cursor:Color::White,
+    hover:Color::Blue,
+    preview:Color::Magenta,
+    ascii:false,
+    colors:Colors::True
+};
+
+impl Theme {
+    //lines of the theme file that don't make sense are skipped, whatever is missing stays classic
+    pub fn load(name:&str,ascii:bool,colors:Colors) -> Theme {
+        let text = fs::read_to_string(format!("{}/{}.txt",THEMES_DIR,name)).unwrap_or_default();
+        let mut theme = CLASSIC;
+        for line in text.lines().filter(|line| !line.starts_with('#')) {
+            theme.set(line);
+        }
+        if colors == Colors::None && theme.wall == ' ' {
+            theme.wall = '█'; //it was only its background
+        }
+        theme.fit(colors);
+        if ascii {
+            theme.ascii = true;
+            (theme.wall,theme.fruit,theme.obstacle) = (theme.sign(theme.wall),theme.sign(theme.fruit),theme.sign(theme.obstacle));
+            theme.heads = theme.heads.map(|head| theme.sign(head));
+            theme.body = theme.body.map(|part| theme.sign(part));
+        }
+        theme
+    }
+
+    //`<what> <glyph or colors...>`
+    fn set(&mut self,line:&str) -> Option<()> {
+        let words = line.split_whitespace().collect::<Vec<&str>>();
+        let (what,values) = words.split_first()?;
+        let value = |index:usize| values.get(index).copied();
+        match *what {
+            "field" => self.field = color(value(0)?)?,
+            "wall" => (self.wall,self.wall_color,self.wall_background) = (glyph(value(0)?)?,color(value(1)?)?,color(value(2)?)?),
+            "spawn" => self.spawn = color(value(0)?)?,
+            "fruit" => (self.fruit,self.fruit_color) = (glyph(value(0)?)?,color(value(1)?)?),
+            "obstacle" => (self.obstacle,self.obstacle_color) = (glyph(value(0)?)?,color(value(1)?)?),
+            "heads" => self.heads = value(0)?.chars().collect::<Vec<char>>().try_into().ok()?,
+            "body" => self.body = value(0)?.chars().collect::<Vec<char>>().try_into().ok()?,
+            "players" => self.players = values.iter().map(|word| color(word)).collect::<Option<Vec<Color>>>()?.try_into().ok()?,
+            "ghost" => self.ghost = color(value(0)?)?,
+            "good" => self.good = color(value(0)?)?,
+            "bad" => self.bad = color(value(0)?)?,
+            "draw" => self.draw = color(value(0)?)?,
+            "selected" => (self.selected,self.selected_background) = (color(value(0)?)?,color(value(1)?)?),
+            "locked" => self.locked = color(value(0)?)?,
+            "cursor" => (self.cursor,self.hover) = (color(value(0)?)?,color(value(1)?)?),
+            "preview" => self.preview = color(value(0)?)?,
+            _ => return None
+        }
+        Some(())
+    }
+
+    //everything goes to the closest color the terminal has
+    fn fit(&mut self,colors:Colors) {
+        self.colors = colors;
+        let fit = |color:Color| fit_color(color,colors);
+        (self.field,self.spawn,self.fruit_color,self.obstacle_color) = (fit(self.field),fit(self.spawn),fit(self.fruit_color),fit(self.obstacle_color));
+        (self.wall_color,self.wall_background) = (fit(self.wall_color),fit(self.wall_background));
+        self.players = self.players.map(fit);
+        (self.ghost,self.good,self.bad,self.draw) = (fit(self.ghost),fit(self.good),fit(self.bad),fit(self.draw));
+        (self.selected,self.selected_background,self.locked) = (fit(self.selected),fit(self.selected_background),fit(self.locked));
+        (self.cursor,self.hover,self.preview) = (fit(self.cursor),fit(self.hover),fit(self.preview));
+    }
+
+    //for colors that aren't in the theme, like the title ones
+    pub fn color(&self,color:Color) -> Color {
+        fit_color(color,self.colors)
+    }
+
+    pub fn head(&self,direction:&Direction) -> char {
+        match direction {
+            Direction::Right => self.heads[0],
+            Direction::Left => self.heads[1],
+            Direction::Down => self.heads[2],
+            Direction::Up => self.heads[3]
+        }
+    }
+
+    //heads and body of that player, the same for everybody as long as colors tell them apart
+    pub fn player(&self,player:usize) -> Theme {
+        let player = player % MAX_PLAYERS;
+        if self.colors != Colors::None || player == 0 {return *self}
+        let (heads,body) = if self.ascii {COLORLESS_ASCII[player - 1]} else {COLORLESS_SIGNS[player - 1]};
+        Theme {heads,body,..*self}
+    }
+
+    pub fn wall_sign(&self) -> StyledContent<char> {
+        self.wall.with(self.wall_color).on(self.wall_background)
+    }
+
+    pub fn fruit_sign(&self) -> StyledContent<char> {
+        self.fruit.with(self.fruit_color).on(self.field)
+    }
+
+    pub fn obstacle_sign(&self) -> StyledContent<char> {
+        self.obstacle.with(self.obstacle_color).on(self.field)
+    }
+
+    pub fn sign(&self,sign:char) -> char {
+        if self.ascii {ascii(sign)} else {sign}
+    }
+
+    pub fn text(&self,text:&str) -> String {
+        text.chars().map(|sign| self.sign(sign)).collect()
+    }
+
+    pub fn highlight<T:Display>(&self,line:T) -> StyledContent<T> {
+        if self.colors == Colors::None {return ContentStyle::new().reverse().apply(line)}
+        ContentStyle::new().with(self.selected).on(self.selected_background).apply(line)
+    }
+
+    //cell that is told only by its color, like spawn and shape preview in the editor,
+    //without colors it's the sign that tells them from each other and from the cursor
+    pub fn block(&self,color:Color,sign:char) -> StyledContent<char> {
+        if self.colors == Colors::None {self.sign(sign).reverse()} else {' '.on(color)}
+    }
+
+    pub fn cursor_sign(&self,hover:bool) -> StyledContent<char> {
+        match (self.colors,hover) {
+            (Colors::None,true) => '+'.reverse(),
+            (Colors::None,false) => ' '.reverse(),
+            _ => ' '.on(if hover {self.hover} else {self.cursor})
+        }
+    }
+
+    //without colors the ghost would look just like the snake, so it's dots with a ring for the head
+    pub fn ghost_sign(&self,sign:char) -> StyledContent<char> {
+        let sign = match (self.colors,self.heads.contains(&sign)) {
+            (Colors::None,true) => self.sign('○'),
+            (Colors::None,false) => self.sign('·'),
+            _ => sign
+        };
+        sign.with(self.ghost).on(self.field)
+    }
+}
+
+//plain look-alike of every sign the game and its pictures have, the rest become stars
+fn ascii(sign:char) -> char {
+    match sign {
+        _ if sign.is_ascii() => sign,
+        '─' | '━' | '═' => '-',
+        '│' | '┃' | '║' => '|',
+        '┌'..='╬' => '+', //corners and crossings of the box drawing
+        '►' => '>',
+        '◄' => '<',
+        '▼' => 'v',
+        '▲' => '^',
+        '▘' | '▝' | '▖' | '▗' => '.', //quarters of the map thumbnails, the more is filled the denser the sign
+        '▀' | '▄' | '▌' | '▐' | '▞' | '▚' => ':',
+        '▛' | '▜' | '▙' | '▟' => '%',
+        '█' => '#',
+        '▓' | '▒' | '░' => 'X', //obstacles shouldn't look like walls
+        '·' => '.',
+        '○' => 'o',
+        '¤' | '●' | '♥' => '@',
+        '☆' | '▫' => '.',
+        _ => '*'
+    }
+}
+
+//terminals that aren't told about UTF-8 show box drawing as garbage, no locale at all is usually Windows where it's fine
+pub fn unicode_locale() -> bool {
+    let locale = ["LC_ALL","LC_CTYPE","LANG"].iter().find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()));
+    locale.is_none_or(|locale| {
+        let locale = locale.to_lowercase();
+        locale.contains("utf-8") || locale.contains("utf8")
+    })
+}
+
+//NO_COLOR is how colors are turned off everywhere, the rest is what terminals say about themselves
+pub fn terminal_colors() -> Colors {
+    let var = |name:&str| std::env::var(name).unwrap_or_default();
+    if !var("NO_COLOR").is_empty() {return Colors::None}
+    if matches!(var("COLORTERM").as_str(),"truecolor" | "24bit") {return Colors::True}
+    let term = var("TERM");
+    match term.as_str() {
+        "" => Colors::True, //Windows doesn't set it and its consoles have RGB
+        "dumb" => Colors::None,
+        _ if term.contains("256") => Colors::Ansi256,
+        _ => Colors::Ansi16
+    }
+}
+
+fn fit_color(color:Color,colors:Colors) -> Color {
+    match (colors,color) {
+        (Colors::None,_) => Color::Reset,
+        (Colors::Ansi256,Color::Rgb {r,g,b}) => Color::AnsiValue(ansi256((r,g,b))),
+        (Colors::Ansi16,_) => rgb(color).map_or(color,ansi16),
+        _ => color
+    }
+}
+
+//only for colors that are not in the 16 ones
+fn rgb(color:Color) -> Option<(u8,u8,u8)> {
+    match color {
+        Color::Rgb {r,g,b} => Some((r,g,b)),
+        Color::AnsiValue(number @ 16..=231) => {
+            let number = (number - 16) as usize;
+            Some((CUBE_LEVELS[number / 36],CUBE_LEVELS[number / 6 % 6],CUBE_LEVELS[number % 6]))
+        },
+        Color::AnsiValue(number @ 232..) => {
+            let gray = 8 + 10 * (number - 232);
+            Some((gray,gray,gray))
+        },
+        _ => None
+    }
+}
+
+fn distance(first:(u8,u8,u8),second:(u8,u8,u8)) -> u32 {
+    let channel = |a:u8,b:u8| (a as i32 - b as i32).pow(2) as u32;
+    channel(first.0,second.0) + channel(first.1,second.1) + channel(first.2,second.2)
+}
+
+//closest of the color cube and the gray ramp
+fn ansi256(color:(u8,u8,u8)) -> u8 {
+    let level = |channel:u8| CUBE_LEVELS.iter().enumerate().min_by_key(|(_,level)| channel.abs_diff(**level)).unwrap().0 as u8;
+    let cube = 16 + 36 * level(color.0) + 6 * level(color.1) + level(color.2);
+    let average = (color.0 as u16 + color.1 as u16 + color.2 as u16) / 3;
+    let gray = 232 + (average.saturating_sub(3) / 10).min(23) as u8;
+    let away = |number:u8| distance(rgb(Color::AnsiValue(number)).unwrap(),color);
+    if away(gray) < away(cube) {gray} else {cube}
+}
+
+fn ansi16(color:(u8,u8,u8)) -> Color {
+    ANSI16.iter().min_by_key(|(_,rgb)| distance(*rgb,color)).unwrap().0
+}
+
+//`space` is the only glyph that can't be written as it is
+fn glyph(word:&str) -> Option<char> {
+    if word == "space" {return Some(' ')}
+    let mut chars = word.chars();
+    let glyph = chars.next()?;
+    chars.next().is_none().then_some(glyph)
+}
+
+//`reset`, a name like `dark_grey`, `#rrggbb` or a number of the 256 colors
+fn color(word:&str) -> Option<Color> {
+    if word == "reset" {return Some(Color::Reset)}
+    if let Some(hex) = word.strip_prefix('#') {
+        let channel = |at:usize| u8::from_str_radix(hex.get(at..at + 2)?,16).ok();
+        return (hex.len() == 6).then_some(Color::Rgb {r:channel(0)?,g:channel(2)?,b:channel(4)?})
+    }
+    if let Ok(number) = word.parse::<u8>() {return Some(Color::AnsiValue(number))}
+    Color::try_from(word).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_and_glyphs() {
+        assert!(color("reset") == Some(Color::Reset) && color("dark_grey") == Some(Color::DarkGrey));
+        assert!(color("#ff8000") == Some(Color::Rgb {r:255,g:128,b:0}) && color("123") == Some(Color::AnsiValue(123)));
+        for word in ["#ff80","#gg0000","256","purplish"] {
+            assert!(color(word).is_none(),"{}",word);
+        }
+        assert!(glyph("space") == Some(' ') && glyph("▓") == Some('▓') && glyph("ab").is_none());
+        //a broken line is skipped and the rest of the theme stays
+        let mut theme = CLASSIC;
+        assert!(theme.set("fruit @ #00ff00").is_some() && theme.set("obstacle X").is_none() && theme.set("heads ><").is_none());
+        assert!(theme.fruit == '@' && theme.fruit_color == Color::Rgb {r:0,g:255,b:0} && theme.obstacle == CLASSIC.obstacle);
+    }
+
+    #[test]
+    fn closest_colors() {
+        assert_eq!(ansi256((255,0,0)),196);
+        assert_eq!(ansi256((0,0,0)),16);
+        assert_eq!(ansi256((128,128,128)),244); //gray ramp is closer than the cube
+        assert_eq!(ansi256((40,40,40)),235);
+        assert!(ansi16((250,10,10)) == Color::Red && ansi16((0,120,130)) == Color::DarkCyan);
+        assert!(fit_color(Color::AnsiValue(196),Colors::Ansi16) == Color::Red);
+        assert!(fit_color(Color::Green,Colors::Ansi256) == Color::Green && fit_color(Color::Green,Colors::None) == Color::Reset);
+    }
+
+    #[test]
+    fn colorless_players() {
+        let theme = Theme {colors:Colors::None,..CLASSIC};
+        let heads = (0..MAX_PLAYERS).map(|player| theme.player(player).heads[0]).collect::<Vec<char>>();
+        assert!(heads.iter().enumerate().all(|(i,head)| !heads[i + 1..].contains(head)));
+        assert!(theme.player(MAX_PLAYERS).heads == CLASSIC.heads && CLASSIC.player(2).body == CLASSIC.body);
+        assert!(theme.ghost_sign(CLASSIC.heads[0]).content() != &CLASSIC.heads[0]);
+    }
+}
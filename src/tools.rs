@@ -0,0 +1,346 @@
+use std::io::stdout;
+use crossterm::{
+    queue,
+    cursor::MoveTo,
+    style::{Print,Stylize}
+};
+
+use crate::{
+    Pos,
+    Button,
+    GAME_FIELD_SIZE,
+    is_spawn,
+    map::{Map,Walls},
+    obstacles::ObstacleFunctionality,
+    theme::Theme,
+    general::error_handling::TerminalHandling
+};
+
+#[derive(Clone,Copy,PartialEq)]
+pub enum Tool {
+    Pen,
+    Line,
+    Rect, //outline only
+    Box, //filled rectangle
+    Fill
+}
+
+#[derive(Clone,Copy,PartialEq)]
+pub enum Mirror {
+    Off,
+    Horizontal, //left half is reflected to the right one
+    Vertical,
+    Both
+}
+
+const TOOLS:[(Tool,&str);5] = [
+    (Tool::Pen,"PEN"),
+    (Tool::Line,"LINE"),
+    (Tool::Rect,"RECT"),
+    (Tool::Box,"BOX"),
+    (Tool::Fill,"FILL")
+];
+const MODES:[(bool,&str);2] = [(true,"PAINT"),(false,"ERASE")];
+const MIRRORS:[(Mirror,&str);4] = [
+    (Mirror::Off,"OFF"),
+    (Mirror::Horizontal,"H"),
+    (Mirror::Vertical,"V"),
+    (Mirror::Both,"4-WAY")
+];
+const ROW_TITLES:[&str;3] = ["Tool:","Mode:","Mirror:"];
+const ITEMS_OFFSET:u16 = 8; //titles column width
+const ITEMS_GAP:u16 = 2;
+
+//what the editor draws with, tool palette is shown under the hint
+pub struct Palette {
+    pub tool:Tool,
+    pub paint:bool, //false = erase
+    pub mirror:Mirror,
+    pub anchor:Option<Pos>, //first corner of line or rectangle in progress
+    x:u16,
+    y:u16
+}
+
+impl Palette {
+    pub fn new(x:u16,y:u16) -> Palette {
+        Palette {tool:Tool::Pen,paint:true,mirror:Mirror::Off,anchor:None,x,y}
+    }
+
+    //line and rectangles need two points, the rest are applied right away
+    pub fn is_shape(&self) -> bool {
+        matches!(self.tool,Tool::Line | Tool::Rect | Tool::Box)
+    }
+
+    //all palette entries as clickable buttons, returns (button,row,index in row)
+    fn items(&self) -> Vec<(Button,usize,usize)> {
+        let rows:[Vec<&str>;3] = [
+            TOOLS.iter().map(|(_,name)| *name).collect(),
+            MODES.iter().map(|(_,name)| *name).collect(),
+            MIRRORS.iter().map(|(_,name)| *name).collect()
+        ];
+        let mut items = vec![];
+        for (row,names) in rows.iter().enumerate() {
+            let mut x = self.x + ITEMS_OFFSET;
+            for (index,name) in names.iter().enumerate() {
+                let width = name.chars().count() as u16;
+                items.push((Button {
+                    x,
+                    y: self.y + row as u16,
+                    width: width - 1,
+                    height: 0,
+                    return_code: 0
+                },row,index));
+                x += width + ITEMS_GAP;
+            }
+        }
+        items
+    }
+
+    fn is_selected(&self,row:usize,index:usize) -> bool {
+        match row {
+            0 => TOOLS[index].0 == self.tool,
+            1 => MODES[index].0 == self.paint,
+            _ => MIRRORS[index].0 == self.mirror
+        }
+    }
+
+    pub fn is_over(&self,x:u16,y:u16) -> bool {
+        self.items().iter().any(|(button,_,_)| hit(button,x,y))
+    }
+
+    //selects entry under cursor, tells if there was one
+    pub fn click(&mut self,x:u16,y:u16) -> bool {
+        let Some((_,row,index)) = self.items().into_iter().find(|(button,_,_)| hit(button,x,y)) else {return false};
+        match row {
+            0 => self.tool = TOOLS[index].0,
+            1 => self.paint = MODES[index].0,
+            _ => self.mirror = MIRRORS[index].0
+        }
+        self.anchor = None;
+        true
+    }
+
+    //1-5 pick a tool, space swaps paint and erase, M goes through mirrors
+    pub fn key(&mut self,key:char) -> bool {
+        match key {
+            '1'..='5' => self.tool = TOOLS[key as usize - '1' as usize].0,
+            ' ' => self.paint = !self.paint,
+            'm' | 'M' => {
+                let next = MIRRORS.iter().position(|(mirror,_)| *mirror == self.mirror).unwrap_or(0) + 1;
+                self.mirror = MIRRORS[next % MIRRORS.len()].0;
+            },
+            _ => return false
+        }
+        self.anchor = None;
+        true
+    }
+
+    pub fn draw(&self,theme:&Theme) {
+        for (row,title) in (0u16..).zip(ROW_TITLES) {
+            queue!(stdout(),MoveTo(self.x,self.y + row),Print(title)).handle();
+        }
+        let names = TOOLS.iter().map(|(_,name)| *name).chain(MODES.iter().map(|(_,name)| *name)).chain(MIRRORS.iter().map(|(_,name)| *name));
+        for ((button,row,index),name) in self.items().into_iter().zip(names) {
+            let label = if self.is_selected(row,index) {theme.highlight(name)} else {name.reset()};
+            queue!(stdout(),MoveTo(button.x,button.y),Print(label)).handle();
+        }
+    }
+
+    //cells the current tool touches when used from `from` to `to`, mirrored copies included
+    pub fn cells(&self,walls:&Walls,from:Pos,to:Pos) -> Vec<Pos> {
+        let mut cells = vec![];
+        for (from,to) in mirrored(from,self.mirror).into_iter().zip(mirrored(to,self.mirror)) {
+            cells.extend(match self.tool {
+                Tool::Pen | Tool::Line => line(from,to),
+                Tool::Rect => rect(from,to,false),
+                Tool::Box => rect(from,to,true),
+                Tool::Fill => fill(walls,to)
+            });
+        }
+        cells
+    }
+
+    //spawns that fit stay playable and obstacles keep their ways, erasing is always fine,
+    //tells if any cell really changed, so a click on what's already there isn't a step of history
+    pub fn apply(&self,map:&mut Map,cells:&[Pos]) -> bool {
+        let players = map.spawn.players(&map.walls);
+        let mut changed = false;
+        for cell in cells {
+            if self.paint && is_spawn(&map.spawn,cell.x,cell.y) {continue}
+            let (x,y) = (cell.x as usize,cell.y as usize);
+            let was = map.walls[y][x];
+            map.walls[y][x] = self.paint;
+            let blocked = map.spawn.players(&map.walls) < players; //somebody's snake or the cell in front of it
+            if self.paint && (blocked || map.obstacles.iter().any(|obstacle| !obstacle.fits(&map.walls))) {
+                map.walls[y][x] = was; //the obstacle would be dropped next time the map is loaded, the spawn refused
+            }
+            changed |= map.walls[y][x] != was;
+        }
+        changed
+    }
+}
+
+fn hit(button:&Button,x:u16,y:u16) -> bool {
+    x.checked_sub(button.x).is_some_and(|dx| dx <= button.width) &&
+        y.checked_sub(button.y).is_some_and(|dy| dy <= button.height)
+}
+
+//the point itself and its reflections
+fn mirrored(pos:Pos,mirror:Mirror) -> Vec<Pos> {
+    let last = (GAME_FIELD_SIZE - 1) as u16;
+    let flip_x = Pos {x:last - pos.x,y:pos.y};
+    let flip_y = Pos {x:pos.x,y:last - pos.y};
+    match mirror {
+        Mirror::Off => vec![pos],
+        Mirror::Horizontal => vec![pos,flip_x],
+        Mirror::Vertical => vec![pos,flip_y],
+        Mirror::Both => vec![pos,flip_x,flip_y,Pos {x:last - pos.x,y:last - pos.y}]
+    }
+}
+
+//bresenham, so a fast mouse drag doesn't leave holes either
+pub fn line(from:Pos,to:Pos) -> Vec<Pos> {
+    let (mut x,mut y) = (from.x as i32,from.y as i32);
+    let (dx,dy) = ((to.x as i32 - x).abs(),-(to.y as i32 - y).abs());
+    let (sx,sy) = (if x < to.x as i32 {1} else {-1},if y < to.y as i32 {1} else {-1});
+    let mut error = dx + dy;
+    let mut cells = vec![];
+    loop {
+        cells.push(Pos {x:x as u16,y:y as u16});
+        if x == to.x as i32 && y == to.y as i32 {break}
+        let double = 2 * error;
+        if double >= dy {
+            error += dy;
+            x += sx;
+        }
+        if double <= dx {
+            error += dx;
+            y += sy;
+        }
+    }
+    cells
+}
+
+fn rect(from:Pos,to:Pos,filled:bool) -> Vec<Pos> {
+    let (left,right) = (from.x.min(to.x),from.x.max(to.x));
+    let (top,bottom) = (from.y.min(to.y),from.y.max(to.y));
+    let mut cells = vec![];
+    for y in top..=bottom {
+        for x in left..=right {
+            if filled || x == left || x == right || y == top || y == bottom {
+                cells.push(Pos {x,y});
+            }
+        }
+    }
+    cells
+}
+
+//all cells connected to `from` that are the same as it (walls or free), field edges stop it
+fn fill(walls:&Walls,from:Pos) -> Vec<Pos> {
+    let target = walls[from.y as usize][from.x as usize];
+    let mut visited = [[false;GAME_FIELD_SIZE];GAME_FIELD_SIZE];
+    visited[from.y as usize][from.x as usize] = true;
+    let mut stack = vec![from];
+    let mut cells = vec![];
+    while let Some(pos) = stack.pop() {
+        cells.push(pos);
+        let (x,y) = (pos.x as usize,pos.y as usize);
+        let neighbours = [
+            (x.wrapping_sub(1),y),
+            (x + 1,y),
+            (x,y.wrapping_sub(1)),
+            (x,y + 1)
+        ];
+        for (x,y) in neighbours {
+            if x >= GAME_FIELD_SIZE || y >= GAME_FIELD_SIZE || visited[y][x] || walls[y][x] != target {continue}
+            visited[y][x] = true;
+            stack.push(Pos {x:x as u16,y:y as u16});
+        }
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        MAX_PLAYERS,
+        next_pos,
+        map::{MapFunctionality,reset_map},
+        obstacles::Obstacle
+    };
+
+    fn pos(x:u16,y:u16) -> Pos {
+        Pos {x,y}
+    }
+
+    #[test]
+    fn lines_have_no_holes() {
+        assert!(line(pos(2,2),pos(2,2)) == [pos(2,2)]);
+        assert!(line(pos(5,3),pos(2,3)) == [pos(5,3),pos(4,3),pos(3,3),pos(2,3)]);
+        assert!(line(pos(1,1),pos(3,3)) == [pos(1,1),pos(2,2),pos(3,3)]);
+        //every next cell touches the previous one, even a steep line
+        let cells = line(pos(3,1),pos(5,9));
+        assert!(cells.first() == Some(&pos(3,1)) && cells.last() == Some(&pos(5,9)) && cells.len() == 9);
+        assert!(cells.windows(2).all(|pair| pair[0].x.abs_diff(pair[1].x) <= 1 && pair[0].y.abs_diff(pair[1].y) == 1));
+    }
+
+    #[test]
+    fn fill_stops_at_walls() {
+        let mut walls = [[false;GAME_FIELD_SIZE];GAME_FIELD_SIZE];
+        reset_map(&mut walls);
+        for cell in rect(pos(5,5),pos(8,8),false) {
+            walls[cell.y as usize][cell.x as usize] = true;
+        }
+        let inside = fill(&walls,pos(6,6));
+        assert_eq!(inside.len(),4);
+        let outside = fill(&walls,pos(1,1));
+        assert_eq!(outside.len(),(GAME_FIELD_SIZE - 2).pow(2) - 16);
+        assert!(!outside.contains(&pos(6,6)));
+        //the walls themselves, border and the box aren't connected
+        assert_eq!(fill(&walls,pos(0,0)).len(),4 * (GAME_FIELD_SIZE - 1));
+        assert_eq!(fill(&Map::new().walls,pos(5,5)).len(),(GAME_FIELD_SIZE - 2).pow(2));
+    }
+
+    #[test]
+    fn mirrors_every_cell() {
+        let mut palette = Palette::new(0,0);
+        palette.key('m');
+        palette.key('m');
+        palette.key('m'); //4-way
+        let cells = palette.cells(&Map::new().walls,pos(2,3),pos(2,3));
+        assert!(cells == [pos(2,3),pos(17,3),pos(2,16),pos(17,16)]);
+    }
+
+    #[test]
+    fn keeps_obstacles_alive() {
+        let mut map = Map::new();
+        map.obstacles.push(Obstacle::parse("patrol 3 10 8 10",&map.walls).unwrap());
+        let palette = Palette::new(0,0);
+        assert!(palette.apply(&mut map,&line(pos(5,8),pos(5,12))));
+        assert!(!map.walls[10][5] && map.walls[9][5] && map.walls[11][5]);
+        assert!(map.obstacles[0].fits(&map.walls));
+        //nothing left to paint there, on the border either
+        assert!(!palette.apply(&mut map,&line(pos(5,8),pos(5,12))));
+        assert!(!palette.apply(&mut map,&[pos(0,0)]));
+    }
+
+    #[test]
+    fn keeps_spawns_playable() {
+        let mut map = Map::new();
+        let palette = Palette::new(0,0);
+        for player in 0..MAX_PLAYERS {
+            let spawn = map.spawn.player(player);
+            let ahead = next_pos(&spawn.pos,&spawn.direction);
+            assert!(!palette.apply(&mut map,&[ahead]));
+        }
+        assert_eq!(map.spawn.players(&map.walls),MAX_PLAYERS);
+        assert!(palette.apply(&mut map,&[pos(10,10)]));
+        //a map that is for one player already can be walled up around the others
+        let second = map.spawn.player(1);
+        map.walls[second.pos.y as usize][second.pos.x as usize] = true;
+        assert_eq!(map.spawn.players(&map.walls),1);
+        let third = map.spawn.player(2);
+        assert!(palette.apply(&mut map,&[next_pos(&third.pos,&third.direction)]));
+    }
+}